@@ -0,0 +1,38 @@
+//! Validated bases.
+
+use std::convert::TryFrom;
+
+use crate::{index, TrinoiseError};
+
+/// A base that is validated to be at least `2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Base(u8);
+
+impl Base {
+    /// The smallest valid base.
+    pub const MIN: u8 = 2;
+
+    /// Creates a new base.
+    pub fn new(base: u8) -> Result<Base, TrinoiseError> {
+        if base < Base::MIN {Err(TrinoiseError::BaseTooSmall(base))} else {Ok(Base(base))}
+    }
+
+    /// Returns the base as a number.
+    pub fn get(self) -> u8 {self.0}
+
+    /// Returns the number of nodes `n^n`.
+    ///
+    /// Returns `TrinoiseError::Overflow` if the number does not fit in `u64`.
+    pub fn end(self) -> Result<u64, TrinoiseError> {
+        index::end(self.0).ok_or(TrinoiseError::Overflow)
+    }
+}
+
+impl TryFrom<u8> for Base {
+    type Error = TrinoiseError;
+    fn try_from(base: u8) -> Result<Base, TrinoiseError> {Base::new(base)}
+}
+
+impl From<Base> for u8 {
+    fn from(base: Base) -> u8 {base.0}
+}
@@ -0,0 +1,160 @@
+//! Arbitrary-precision unsigned integers.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// An arbitrary-precision unsigned integer.
+///
+/// This is used as index type for bases where `n^n` does not fit in `u128`.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigUint {
+    // Little endian limbs without trailing zeros.
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    /// Returns zero.
+    pub fn zero() -> BigUint {BigUint {limbs: vec![]}}
+
+    /// Returns `true` if the number is zero.
+    pub fn is_zero(&self) -> bool {self.limbs.is_empty()}
+
+    /// Converts to `u64`, returning `None` if the number is too large.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some(self.limbs[0] as u64 | (self.limbs[1] as u64) << 32),
+            _ => None,
+        }
+    }
+
+    /// Adds two numbers.
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let n = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(n + 1);
+        let mut carry = 0;
+        for i in 0..n {
+            let a = *self.limbs.get(i).unwrap_or(&0) as u64;
+            let b = *other.limbs.get(i).unwrap_or(&0) as u64;
+            let s = a + b + carry;
+            limbs.push(s as u32);
+            carry = s >> 32;
+        }
+        if carry > 0 {limbs.push(carry as u32)}
+        BigUint {limbs}
+    }
+
+    /// Subtracts a number, returning `None` if the result is negative.
+    pub fn sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {return None}
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0;
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let b = *other.limbs.get(i).unwrap_or(&0) as i64;
+            let mut d = a as i64 - b - borrow;
+            if d < 0 {d += 1 << 32; borrow = 1} else {borrow = 0}
+            limbs.push(d as u32);
+        }
+        Some(BigUint {limbs}.normalized())
+    }
+
+    /// Multiplies with a small number.
+    pub fn mul_small(&self, m: u32) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = 0;
+        for &a in &self.limbs {
+            let p = a as u64 * m as u64 + carry;
+            limbs.push(p as u32);
+            carry = p >> 32;
+        }
+        if carry > 0 {limbs.push(carry as u32)}
+        BigUint {limbs}.normalized()
+    }
+
+    /// Divides by a small number, returning the quotient and the remainder.
+    ///
+    /// Panics if `d` is zero.
+    pub fn div_rem_small(&self, d: u32) -> (BigUint, u32) {
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut rem = 0;
+        for i in (0..self.limbs.len()).rev() {
+            let x = rem << 32 | self.limbs[i] as u64;
+            limbs[i] = (x / d as u64) as u32;
+            rem = x % d as u64;
+        }
+        (BigUint {limbs}.normalized(), rem as u32)
+    }
+
+    fn normalized(mut self) -> BigUint {
+        while self.limbs.last() == Some(&0) {self.limbs.pop();}
+        self
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(v: u64) -> BigUint {
+        BigUint {limbs: vec![v as u32, (v >> 32) as u32]}.normalized()
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {Some(self.cmp(other))}
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {return write!(f, "0")}
+        let mut chunks = vec![];
+        let mut v = self.clone();
+        while !v.is_zero() {
+            let (q, r) = v.div_rem_small(1_000_000_000);
+            chunks.push(r);
+            v = q;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for c in chunks.iter().rev() {write!(f, "{:09}", c)?}
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = BigUint::from(u64::MAX);
+        let b = a.add(&BigUint::from(1));
+        assert_eq!(format!("{}", b), "18446744073709551616");
+        assert_eq!(b.sub(&BigUint::from(1)), Some(a.clone()));
+        assert_eq!(a.sub(&b), None);
+        assert_eq!(b.to_u64(), None);
+        assert_eq!(a.to_u64(), Some(u64::MAX));
+
+        let mut c = BigUint::from(1);
+        for _ in 0..30 {c = c.mul_small(1000)}
+        assert_eq!(format!("{}", c), format!("1{}", "0".repeat(90)));
+        for _ in 0..30 {
+            let (q, r) = c.div_rem_small(1000);
+            assert_eq!(r, 0);
+            c = q;
+        }
+        assert_eq!(c, BigUint::from(1));
+        assert_eq!(BigUint::from(0), BigUint::zero());
+        assert!(BigUint::from(3) < b);
+    }
+}
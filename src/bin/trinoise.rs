@@ -0,0 +1,185 @@
+//! Command line interface to the trinoise core functions.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use trinoise::{index, trn, Base, BigUint, Signature, TrinoiseError};
+
+const USAGE: &str = "\
+Usage: trinoise [--format text|csv|json] <command>
+
+Commands:
+    signature <base>        Prints the signature of some base
+    aligned <v> <base>      Prints the aligned positions to identity map
+    next <v> <base>         Prints the successors with shared aligned positions
+    freq <base>             Prints the frequencies of `0`, `1` and `2`
+    export <base> <file>    Writes the signature to a `.trn` file
+";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {Text, Csv, Json}
+
+fn parse_base(s: &str) -> Result<u8, String> {
+    let base: u8 = s.parse().map_err(|_| format!("Invalid base `{}`", s))?;
+    Base::new(base).map_err(|err| err.to_string())?;
+    Ok(base)
+}
+
+fn parse_index(s: &str) -> Result<BigUint, String> {
+    if s.is_empty() {return Err("Expected number".into())}
+    let mut v = BigUint::zero();
+    for ch in s.chars() {
+        let d = ch.to_digit(10).ok_or_else(|| format!("Invalid number `{}`", s))?;
+        v = v.mul_small(10).add(&BigUint::from(d as u64));
+    }
+    Ok(v)
+}
+
+/// Formats a string as a JSON string literal, escaping quotes and control characters.
+fn json_string(s: &str) -> String {
+    let mut r = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => r.push_str("\\\""),
+            '\\' => r.push_str("\\\\"),
+            '\n' => r.push_str("\\n"),
+            c if (c as u32) < 0x20 => r.push_str(&format!("\\u{:04x}", c as u32)),
+            c => r.push(c),
+        }
+    }
+    r.push('"');
+    r
+}
+
+fn run<W: Write>(w: &mut W, format: Format, args: &[String]) -> Result<(), String> {
+    let io_err = |err: io::Error| err.to_string();
+    match args {
+        [cmd, base] if cmd == "signature" => {
+            let base = parse_base(base)?;
+            let sig = Base::new(base).and_then(Signature::new).map_err(|err| err.to_string())?;
+            match format {
+                Format::Text => {
+                    for x in sig {write!(w, "{}", x).map_err(io_err)?}
+                    writeln!(w).map_err(io_err)?;
+                }
+                Format::Csv => {
+                    writeln!(w, "index,value").map_err(io_err)?;
+                    for (i, x) in sig.iter().enumerate() {
+                        writeln!(w, "{},{}", i, x).map_err(io_err)?;
+                    }
+                }
+                Format::Json => {
+                    write!(w, "{{\"base\":{},\"signature\":[", base).map_err(io_err)?;
+                    for (i, x) in sig.iter().enumerate() {
+                        if i > 0 {write!(w, ",").map_err(io_err)?}
+                        write!(w, "{}", x).map_err(io_err)?;
+                    }
+                    writeln!(w, "]}}").map_err(io_err)?;
+                }
+            }
+        }
+        [cmd, v, base] if cmd == "aligned" || cmd == "next" => {
+            let v = parse_index(v)?;
+            let base = parse_base(base)?;
+            let end = index::end::<BigUint>(base).unwrap();
+            if v >= end {return Err(TrinoiseError::IndexOutOfRange.to_string())}
+            let x = if cmd == "aligned" {index::aligned(&v, base)}
+                // The end has no successors, like `checked_next`.
+                else if v.add(&BigUint::from(1)) == end {0}
+                else {index::next(&v, base)};
+            match format {
+                Format::Text => writeln!(w, "{}", x),
+                Format::Csv => writeln!(w, "v,base,{}\n{},{},{}", cmd, v, base, x),
+                Format::Json => writeln!(w, "{{\"v\":{},\"base\":{},\"{}\":{}}}", v, base, cmd, x),
+            }.map_err(io_err)?;
+        }
+        [cmd, base] if cmd == "freq" => {
+            let base = parse_base(base)?;
+            let p = index::frequencies::<BigUint>(base).unwrap();
+            match format {
+                Format::Text => writeln!(w, "{} {} {}", p[0], p[1], p[2]),
+                Format::Csv => writeln!(w, "base,p0,p1,p2\n{},{},{},{}", base, p[0], p[1], p[2]),
+                Format::Json => writeln!(w, "{{\"base\":{},\"frequencies\":[{},{},{}]}}",
+                                         base, p[0], p[1], p[2]),
+            }.map_err(io_err)?;
+        }
+        [cmd, base, file] if cmd == "export" => {
+            let base = parse_base(base)?;
+            Base::new(base).and_then(|b| b.end()).map_err(|err| err.to_string())?;
+            let mut f = BufWriter::new(File::create(file).map_err(io_err)?);
+            let header = trn::write_signature(&mut f, base).map_err(io_err)?;
+            f.flush().map_err(io_err)?;
+            match format {
+                Format::Text => writeln!(w, "Wrote {} values to `{}`", header.len, file),
+                Format::Csv => writeln!(w, "file,base,len,checksum\n{},{},{},{}",
+                                        file, base, header.len, header.checksum),
+                Format::Json => writeln!(w, "{{\"file\":{},\"base\":{},\"len\":{},\"checksum\":{}}}",
+                                         json_string(file), base, header.len, header.checksum),
+            }.map_err(io_err)?;
+        }
+        _ => return Err(USAGE.into()),
+    }
+    w.flush().map_err(io_err)
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut format = Format::Text;
+    if let Some(i) = args.iter().position(|a| a == "--format" || a == "-f") {
+        format = match args.get(i + 1).map(|s| &**s) {
+            Some("text") => Format::Text,
+            Some("csv") => Format::Csv,
+            Some("json") => Format::Json,
+            _ => {
+                eprintln!("Expected `text`, `csv` or `json` after `--format`");
+                process::exit(2);
+            }
+        };
+        args.drain(i..i + 2);
+    }
+    let stdout = io::stdout();
+    let mut w = BufWriter::new(stdout.lock());
+    if let Err(err) = run(&mut w, format, &args) {
+        eprint!("{}", err);
+        if !err.ends_with('\n') {eprintln!()}
+        process::exit(if err == USAGE {2} else {1});
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(format: Format, args: &[&str]) -> Result<String, String> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let mut buf = vec![];
+        run(&mut buf, format, &args)?;
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    #[test]
+    fn commands() {
+        let sig = output(Format::Text, &["signature", "3"]).unwrap();
+        assert_eq!(sig.trim(), trinoise::signature(3).iter().map(|x| x.to_string()).collect::<String>());
+        assert_eq!(output(Format::Text, &["aligned", "5", "3"]), Ok("3\n".into()));
+        assert_eq!(output(Format::Text, &["next", "2", "3"]), Ok("2\n".into()));
+        assert_eq!(output(Format::Text, &["next", "26", "3"]), Ok("0\n".into()));
+        assert!(output(Format::Text, &["aligned", "27", "3"]).is_err());
+        assert!(output(Format::Text, &["next", "99", "3"]).is_err());
+        assert!(output(Format::Text, &["aligned", "5", "1"]).is_err());
+        assert_eq!(output(Format::Json, &["freq", "3"]),
+                   Ok("{\"base\":3,\"frequencies\":[6,6,3]}\n".into()));
+        assert_eq!(output(Format::Csv, &["aligned", "5", "3"]), Ok("v,base,aligned\n5,3,3\n".into()));
+        assert_eq!(output(Format::Text, &["bogus"]), Err(USAGE.into()));
+
+        let dir = std::env::temp_dir().join(format!("trinoise-cli-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a\tb.trn").to_str().unwrap().to_string();
+        let json = output(Format::Json, &["export", "3", &file]).unwrap();
+        assert!(json.starts_with(&format!("{{\"file\":{},", json_string(&file))));
+        assert!(json.contains("\\u0009"));
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
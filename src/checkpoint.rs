@@ -0,0 +1,238 @@
+//! Checkpoint and resume for long-running signature and frequency jobs.
+//!
+//! A checkpoint is stored as a small text file:
+//!
+//! ```text
+//! trinoise checkpoint 1
+//! base 10
+//! node 1234567
+//! offset 456789
+//! counts 200000 200000 56789
+//! ```
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{neighborhood_len, tri, Counter};
+
+/// The state of a signature or frequency job.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The base of numbers.
+    pub base: u8,
+    /// The first number of the next neighborhood.
+    pub node: u64,
+    /// The number of signature values produced so far.
+    pub offset: u64,
+    /// The frequencies of `0`, `1` and `2` so far.
+    pub counts: [u64; 3],
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Checkpoint {
+    /// Creates a new checkpoint at the start of some base.
+    pub fn new(base: u8) -> Checkpoint {
+        Checkpoint {base, node: 0, offset: 0, counts: [0; 3]}
+    }
+
+    /// Returns the number of nodes `n^n`.
+    pub fn end(&self) -> u64 {(self.base as u64).pow(self.base as u32)}
+
+    /// Returns `true` if all neighborhoods are processed.
+    pub fn is_done(&self) -> bool {self.node >= self.end()}
+
+    /// Advances by at most `limit` neighborhoods, writing one byte per signature value.
+    pub fn advance<W: Write>(&mut self, out: &mut W, limit: u64) -> io::Result<()> {
+        let end = self.end();
+        let mut counter = Counter::new(self.node, self.base);
+        let mut buf = vec![];
+        for _ in 0..limit {
+            if self.node >= end {break}
+            let len = neighborhood_len(&mut counter, self.node, end);
+            let t = tri((len - 1) as u8, self.base);
+            self.node += len;
+            self.offset += 1;
+            self.counts[t as usize] += 1;
+            buf.push(t);
+        }
+        out.write_all(&buf)
+    }
+
+    /// Saves the checkpoint to a file.
+    ///
+    /// Writes to a temporary file first and renames it,
+    /// so a crash never leaves a partially written checkpoint.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        {
+            let mut f = File::create(&tmp)?;
+            write!(f, "trinoise checkpoint 1\nbase {}\nnode {}\noffset {}\ncounts {} {} {}\n",
+                self.base, self.node, self.offset, self.counts[0], self.counts[1], self.counts[2])?;
+            f.sync_all()?;
+        }
+        fs::rename(&tmp, path)
+    }
+
+    /// Loads a checkpoint from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Checkpoint> {
+        let data = fs::read_to_string(path)?;
+        let mut lines = data.lines();
+        if lines.next() != Some("trinoise checkpoint 1") {
+            return Err(invalid("Expected `trinoise checkpoint 1`"));
+        }
+        let mut field = |name: &str| -> io::Result<Vec<u64>> {
+            let line = lines.next().ok_or_else(|| invalid("Unexpected end of checkpoint"))?;
+            let mut words = line.split_whitespace();
+            if words.next() != Some(name) {return Err(invalid(&format!("Expected `{}`", name)))}
+            words.map(|w| w.parse().map_err(|_| invalid(&format!("Invalid `{}`", name)))).collect()
+        };
+        let base = field("base")?;
+        let node = field("node")?;
+        let offset = field("offset")?;
+        let counts = field("counts")?;
+        if base.len() != 1 || base[0] > u8::MAX as u64 || node.len() != 1 ||
+           offset.len() != 1 || counts.len() != 3 {
+            return Err(invalid("Invalid checkpoint"));
+        }
+        Ok(Checkpoint {
+            base: base[0] as u8,
+            node: node[0],
+            offset: offset[0],
+            counts: [counts[0], counts[1], counts[2]],
+        })
+    }
+}
+
+/// A signature or frequency job that periodically saves a checkpoint.
+///
+/// When the job is interrupted, opening it again resumes from the last checkpoint
+/// and produces the same result as an uninterrupted run.
+#[derive(Clone, Debug)]
+pub struct Job {
+    checkpoint: Checkpoint,
+    path: PathBuf,
+    interval: u64,
+}
+
+impl Job {
+    /// Opens a job, resuming from the checkpoint file if it exists.
+    ///
+    /// A checkpoint is saved every `interval` neighborhoods.
+    pub fn open<P: AsRef<Path>>(base: u8, path: P, interval: u64) -> io::Result<Job> {
+        let path = path.as_ref().to_path_buf();
+        let checkpoint = if path.exists() {
+            let c = Checkpoint::load(&path)?;
+            if c.base != base {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                    format!("Checkpoint has base `{}`, expected `{}`", c.base, base)));
+            }
+            c
+        } else {Checkpoint::new(base)};
+        Ok(Job {checkpoint, path, interval: interval.max(1)})
+    }
+
+    /// Returns the current checkpoint.
+    pub fn checkpoint(&self) -> &Checkpoint {&self.checkpoint}
+
+    /// Opens the signature output file to continue where the checkpoint left off.
+    ///
+    /// Values written after the last checkpoint are truncated.
+    /// Returns an `InvalidData` error if the file is shorter than the checkpoint offset,
+    /// since values before the checkpoint would be missing.
+    pub fn open_output<P: AsRef<Path>>(&self, path: P) -> io::Result<File> {
+        let mut f = OpenOptions::new().create(true).truncate(false).write(true).open(path)?;
+        let len = f.metadata()?.len();
+        if len < self.checkpoint.offset {
+            return Err(invalid(&format!("Output has `{}` values, expected at least `{}`",
+                len, self.checkpoint.offset)));
+        }
+        if len > self.checkpoint.offset {f.set_len(self.checkpoint.offset)?}
+        f.seek(SeekFrom::End(0))?;
+        Ok(f)
+    }
+
+    /// Runs the job to the end, writing one byte per signature value.
+    ///
+    /// Returns the frequencies of `0`, `1` and `2`.
+    pub fn run(&mut self, out: &mut File) -> io::Result<[u64; 3]> {
+        self.run_with(out, |f| f.sync_data())
+    }
+
+    /// Runs the job to the end, only counting frequencies.
+    pub fn run_frequencies(&mut self) -> io::Result<[u64; 3]> {
+        self.run_with(&mut io::sink(), |_| Ok(()))
+    }
+
+    fn run_with<W, F>(&mut self, out: &mut W, mut sync: F) -> io::Result<[u64; 3]>
+        where W: Write, F: FnMut(&mut W) -> io::Result<()>
+    {
+        while !self.checkpoint.is_done() {
+            self.checkpoint.advance(out, self.interval)?;
+            // Sync before saving, such that the output is never behind the checkpoint.
+            out.flush()?;
+            sync(out)?;
+            self.checkpoint.save(&self.path)?;
+        }
+        self.checkpoint.save(&self.path)?;
+        Ok(self.checkpoint.counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{frequencies, signature};
+
+    #[test]
+    fn resume() {
+        let dir = std::env::temp_dir().join(format!("trinoise-checkpoint-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let check = dir.join("job.txt");
+        let out = dir.join("out.bin");
+        let base = 5;
+
+        // Interrupted run with progress after the last checkpoint.
+        let mut c = Checkpoint::new(base);
+        let mut f = File::create(&out).unwrap();
+        c.advance(&mut f, 100).unwrap();
+        c.save(&check).unwrap();
+        c.advance(&mut f, 7).unwrap();
+        drop(f);
+        assert_eq!(Checkpoint::load(&check).unwrap().offset, 100);
+
+        let mut job = Job::open(base, &check, 64).unwrap();
+        assert_eq!(job.checkpoint().node, Checkpoint::load(&check).unwrap().node);
+        let mut f = job.open_output(&out).unwrap();
+        let counts = job.run(&mut f).unwrap();
+        drop(f);
+        assert_eq!(fs::read(&out).unwrap(), signature(base));
+        let p = frequencies(base);
+        assert_eq!(counts, [p[0] as u64, p[1] as u64, p[2] as u64]);
+        assert!(Checkpoint::load(&check).unwrap().is_done());
+        assert!(Job::open(4, &check, 64).is_err());
+
+        fs::remove_file(&check).unwrap();
+        let mut job = Job::open(base, &check, 1000).unwrap();
+        assert_eq!(job.run_frequencies().unwrap(), counts);
+
+        // Resuming into a short or fresh output file must fail instead of padding with zeros.
+        let mut c = Checkpoint::new(4);
+        c.advance(&mut io::sink(), 30).unwrap();
+        c.save(&check).unwrap();
+        let job = Job::open(4, &check, 64).unwrap();
+        fs::write(&out, &signature(4)[..10]).unwrap();
+        let err = job.open_output(&out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(&out).unwrap().len(), 10);
+        fs::remove_file(&out).unwrap();
+        assert!(job.open_output(&out).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
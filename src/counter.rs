@@ -0,0 +1,132 @@
+//! Incremental counting of aligned positions.
+
+use crate::{Index, Reference};
+
+/// Counts upwards in base `n` while keeping track of aligned positions to a reference map.
+///
+/// Incrementing only touches the carried digits, so walking through numbers
+/// costs amortized `O(1)` per number instead of recomputing `aligned` from scratch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Counter {
+    base: u8,
+    digits: Vec<u8>,
+    // The digit compared against at each position, starting with the least significant.
+    // This is `None` for the identity map, where position `k` is compared against `n - 1 - k`.
+    targets: Option<Vec<u8>>,
+    aligned: u8,
+}
+
+impl Counter {
+    /// Creates a new counter starting at some number, aligned to identity map.
+    ///
+    /// Only the `base` least significant digits are kept,
+    /// since those are the ones checked by `aligned`.
+    pub fn new(v: u64, base: u8) -> Counter {Counter::from_index(&v, base)}
+
+    /// Creates a new counter starting at some number of any index type.
+    pub fn from_index<T: Index>(v: &T, base: u8) -> Counter {
+        let (digits, aligned) = split(v, base, |k| base - 1 - k as u8);
+        Counter {base, digits, targets: None, aligned}
+    }
+
+    /// Creates a new counter starting at some number, aligned to a reference map.
+    pub fn with_reference<T: Index>(v: &T, reference: &Reference) -> Counter {
+        if reference.is_identity() {return Counter::from_index(v, reference.base())}
+        let base = reference.base();
+        let targets: Vec<u8> = reference.map().iter().rev().cloned().collect();
+        let (digits, aligned) = split(v, base, |k| targets[k]);
+        Counter {base, digits, targets: Some(targets), aligned}
+    }
+
+    /// Returns the number represented by the digits.
+    ///
+    /// Returns `None` if the number does not fit in the index type.
+    pub fn to_index<T: Index>(&self) -> Option<T> {
+        let mut v = T::from_u64(0);
+        for &d in self.digits.iter().rev() {
+            v = v.checked_mul_small(self.base as u32)?.checked_add(&T::from_u64(d as u64))?;
+        }
+        Some(v)
+    }
+
+    /// Returns the base of the counter.
+    pub fn base(&self) -> u8 {self.base}
+
+    /// Returns the digits, starting with the least significant digit.
+    pub fn digits(&self) -> &[u8] {&self.digits}
+
+    /// Returns the number of aligned positions to the reference map.
+    pub fn aligned(&self) -> u8 {self.aligned}
+
+    /// Increments the counter by one.
+    ///
+    /// Returns `true` when the counter wraps around from `n^n - 1` to `0`.
+    pub fn increment(&mut self) -> bool {
+        let n = self.base;
+        match &self.targets {
+            None => increment(&mut self.digits, &mut self.aligned, n, |k| n - 1 - k as u8),
+            Some(targets) => increment(&mut self.digits, &mut self.aligned, n, |k| targets[k]),
+        }
+    }
+}
+
+/// Splits a number into `base` digits, counting those equal to their target.
+fn split<T: Index, F: Fn(usize) -> u8>(v: &T, base: u8, target: F) -> (Vec<u8>, u8) {
+    let mut v = v.clone();
+    let mut digits = Vec::with_capacity(base as usize);
+    let mut aligned = 0;
+    for k in 0..base as usize {
+        let (q, d) = v.div_rem_small(base as u32);
+        if d == target(k) as u32 {aligned += 1}
+        digits.push(d as u8);
+        v = q;
+    }
+    (digits, aligned)
+}
+
+fn increment<F: Fn(usize) -> u8>(digits: &mut [u8], aligned: &mut u8, n: u8, target: F) -> bool {
+    for (k, d) in digits.iter_mut().enumerate() {
+        let t = target(k);
+        if *d == t {*aligned -= 1}
+        if *d + 1 < n {
+            *d += 1;
+            if *d == t {*aligned += 1}
+            return false;
+        }
+        *d = 0;
+        if t == 0 {*aligned += 1}
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aligned;
+
+    #[test]
+    fn increment() {
+        for base in 1..6 {
+            let end = (base as u64).pow(base as u32);
+            let mut c = Counter::new(0, base);
+            for v in 0..end {
+                assert_eq!(c.aligned(), aligned(v, base));
+                assert_eq!(c, Counter::new(v, base));
+                assert_eq!(c.to_index::<u64>(), Some(v));
+                assert_eq!(c.increment(), v + 1 == end);
+            }
+            assert_eq!(c, Counter::new(0, base));
+        }
+
+        // Identity reference uses the same fast path as `Counter::new`.
+        assert_eq!(Counter::with_reference(&7u64, &Reference::identity(3)), Counter::new(7, 3));
+        let r = Reference::new(vec![2, 0, 0]).unwrap();
+        let mut c = Counter::with_reference(&0u64, &r);
+        for v in 0..27u64 {
+            let digits = [v / 9, v / 3 % 3, v % 3];
+            let a = digits.iter().zip(r.map()).filter(|&(&d, &t)| d == t as u64).count();
+            assert_eq!(c.aligned() as usize, a);
+            c.increment();
+        }
+    }
+}
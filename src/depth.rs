@@ -0,0 +1,119 @@
+//! Depth-ordered enumeration of the reachability tree.
+
+use std::ops::Range;
+
+use crate::{index, neighborhood_of, select_aligned, successor_with_aligned, Base, TrinoiseError};
+
+/// Iterates over nodes in order of depth in the reachability tree.
+///
+/// Yields all nodes of depth `0`, then depth `1` and so on,
+/// in ascending order within each depth, together with their depth.
+/// The depth of a node is `n` minus aligned positions with identity map,
+/// which is the smallest number of modifications from the identity map.
+///
+/// Each neighborhood of the current depth is found directly with `select_aligned`
+/// and `successor_with_aligned`, so nodes are never scanned to find the next one.
+#[derive(Clone, Debug)]
+pub struct DepthOrder {
+    base: u8,
+    depth: u8,
+    members: Range<u64>,
+    // Whether `members` is the last neighborhood found at the current depth.
+    started: bool,
+    left: u64,
+}
+
+impl DepthOrder {
+    /// Creates a new depth-ordered iterator of some base.
+    ///
+    /// Returns `TrinoiseError::Overflow` if `n^n` does not fit in `u64`.
+    pub fn new(base: Base) -> Result<DepthOrder, TrinoiseError> {
+        base.end()?;
+        Ok(DepthOrder::from_base(base.get()))
+    }
+
+    /// Creates a new depth-ordered iterator of any base, including `0` and `1`.
+    ///
+    /// Panics if `n^n` does not fit in `u64`.
+    pub(crate) fn from_base(base: u8) -> DepthOrder {
+        DepthOrder {
+            base,
+            depth: 0,
+            members: 0..0,
+            started: false,
+            left: index::end::<u64>(base).expect("`n^n` overflows `u64`"),
+        }
+    }
+}
+
+impl Iterator for DepthOrder {
+    type Item = (u8, u64);
+    fn next(&mut self) -> Option<(u8, u64)> {
+        loop {
+            if let Some(v) = self.members.next() {
+                self.left -= 1;
+                return Some((self.depth, v));
+            }
+            if self.left == 0 {return None}
+            let a = self.base - self.depth;
+            // All nodes fit in `u64`, so there is no overflow.
+            let v = if self.started {
+                successor_with_aligned(self.base, a, self.members.end - 1).unwrap()
+            } else {
+                select_aligned(self.base, a, 0).unwrap()
+            };
+            match v {
+                Some(v) => {
+                    // The first node after the previous neighborhood starts a new neighborhood.
+                    let (n, _) = neighborhood_of(v, self.base).unwrap();
+                    self.members = v..n.start + n.len;
+                    self.started = true;
+                }
+                None => {
+                    self.depth += 1;
+                    self.started = false;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.left as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for DepthOrder {}
+
+impl std::iter::FusedIterator for DepthOrder {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::depth;
+
+    #[test]
+    fn depth_order() {
+        let base = 3;
+        let iter = DepthOrder::new(Base::new(base).unwrap()).unwrap();
+        let nodes: Vec<(u8, u64)> = iter.take(4).collect();
+        assert_eq!(nodes, vec![(0, 5), (1, 2), (1, 3), (1, 4)]);
+        assert!(DepthOrder::new(Base::new(16).unwrap()).is_err());
+
+        // The first nodes are found without scanning all nodes.
+        let mut iter = DepthOrder::new(Base::new(15).unwrap()).unwrap();
+        assert_eq!(iter.next().map(|(d, v)| (d, depth(v, 15))), Some((0, 0)));
+        let (d, v) = iter.next().unwrap();
+        assert_eq!((d, depth(v, 15)), (1, 1));
+        assert_eq!(Ok(Some(v)), select_aligned(15, 14, 0));
+
+        for base in 0..6 {
+            let end = (base as u64).pow(base as u32);
+            let mut nodes: Vec<(u8, u64)> = (0..end).map(|v| (depth(v, base), v)).collect();
+            nodes.sort();
+            let iter = DepthOrder::from_base(base);
+            assert_eq!(iter.len() as u64, end);
+            assert_eq!(iter.collect::<Vec<_>>(), nodes);
+        }
+    }
+}
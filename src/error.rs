@@ -0,0 +1,31 @@
+//! Errors reported by the checked API.
+
+use std::error::Error;
+use std::fmt;
+
+/// An error that occurs when passing invalid input to the checked API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrinoiseError {
+    /// The base is smaller than `2`.
+    BaseTooSmall(u8),
+    /// The index is outside the valid range.
+    IndexOutOfRange,
+    /// The result does not fit in the number type.
+    Overflow,
+    /// A character is not a valid digit when parsing.
+    InvalidDigit(char),
+}
+
+impl fmt::Display for TrinoiseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrinoiseError::BaseTooSmall(base) =>
+                write!(f, "Base `{}` is too small, expected at least `{}`", base, crate::Base::MIN),
+            TrinoiseError::IndexOutOfRange => write!(f, "Index out of range"),
+            TrinoiseError::Overflow => write!(f, "Overflow"),
+            TrinoiseError::InvalidDigit(ch) => write!(f, "Invalid digit `{}`", ch),
+        }
+    }
+}
+
+impl Error for TrinoiseError {}
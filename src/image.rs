@@ -0,0 +1,110 @@
+//! Rendering of signatures and aligned fields as PGM/PPM images.
+//!
+//! Values are laid out row by row in a grid of chosen width,
+//! then mapped to colors through a palette indexed by value.
+
+use std::io::{self, Write};
+
+use crate::Counter;
+
+/// Default grayscale palette for signature values `0`, `1` and `2`.
+pub const GRAY: [u8; 3] = [0, 128, 255];
+
+/// Default RGB palette for signature values `0`, `1` and `2`.
+pub const RGB: [[u8; 3]; 3] = [[20, 20, 60], [60, 160, 90], [250, 220, 80]];
+
+/// A grid of values to render.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+    /// The values, row by row.
+    pub cells: Vec<u8>,
+}
+
+impl Grid {
+    /// Lays out a signature in rows of some width.
+    ///
+    /// The last row is padded with `0`.
+    pub fn from_signature(signature: &[u8], width: usize) -> Grid {
+        let width = width.max(1);
+        let height = signature.len().div_ceil(width);
+        let mut cells = signature.to_vec();
+        cells.resize(width * height, 0);
+        Grid {width, height, cells}
+    }
+
+    /// Lays out the aligned positions of numbers starting at `start`.
+    pub fn aligned_field(base: u8, start: u64, width: usize, height: usize) -> Grid {
+        let mut counter = Counter::new(start, base);
+        let cells = (0..width * height)
+            .map(|_| {
+                let a = counter.aligned();
+                counter.increment();
+                a
+            })
+            .collect();
+        Grid {width, height, cells}
+    }
+
+    /// Writes the grid as binary PGM (`P5`), looking up the gray level of each value.
+    ///
+    /// Values outside the palette are drawn with the last gray level.
+    pub fn write_pgm<W: Write>(&self, w: &mut W, palette: &[u8]) -> io::Result<()> {
+        write!(w, "P5\n{} {}\n255\n", self.width, self.height)?;
+        let pixels: Vec<u8> = self.cells.iter().map(|&x| lookup(palette, x, 0)).collect();
+        w.write_all(&pixels)
+    }
+
+    /// Writes the grid as binary PPM (`P6`), looking up the RGB color of each value.
+    ///
+    /// Values outside the palette are drawn with the last color.
+    pub fn write_ppm<W: Write>(&self, w: &mut W, palette: &[[u8; 3]]) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let mut pixels = Vec::with_capacity(self.cells.len() * 3);
+        for &x in &self.cells {pixels.extend_from_slice(&lookup(palette, x, [0; 3]))}
+        w.write_all(&pixels)
+    }
+}
+
+fn lookup<T: Copy>(palette: &[T], x: u8, default: T) -> T {
+    palette.get(x as usize).or_else(|| palette.last()).cloned().unwrap_or(default)
+}
+
+/// Returns a grayscale palette from black to white with some number of levels.
+///
+/// For the aligned field of base `n`, use `n + 1` levels.
+pub fn gray_ramp(levels: usize) -> Vec<u8> {
+    if levels < 2 {return vec![255; levels]}
+    (0..levels).map(|i| (i * 255 / (levels - 1)) as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aligned, signature};
+
+    #[test]
+    fn render() {
+        let g = Grid::from_signature(&signature(3), 4);
+        assert_eq!((g.width, g.height), (4, 4));
+        assert_eq!(&g.cells[12..], &[0, 1, 0, 0]);
+        let mut pgm = vec![];
+        g.write_pgm(&mut pgm, &GRAY).unwrap();
+        assert!(pgm.starts_with(b"P5\n4 4\n255\n"));
+        assert_eq!(&pgm[11..15], &[128, 255, 0, 128]);
+        let mut ppm = vec![];
+        g.write_ppm(&mut ppm, &RGB).unwrap();
+        assert_eq!(ppm.len(), 11 + 16 * 3);
+        assert_eq!(&ppm[11..14], &RGB[1]);
+
+        let g = Grid::aligned_field(4, 10, 8, 2);
+        for (i, &a) in g.cells.iter().enumerate() {assert_eq!(a, aligned(10 + i as u64, 4))}
+        assert_eq!(gray_ramp(5), vec![0, 63, 127, 191, 255]);
+        let mut pgm = vec![];
+        g.write_pgm(&mut pgm, &gray_ramp(5)).unwrap();
+        assert_eq!(pgm.len(), 11 + 16);
+    }
+}
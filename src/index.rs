@@ -0,0 +1,400 @@
+//! Generic index types for nodes of any base.
+//!
+//! The functions in this module work like those in the crate root,
+//! but are generic over the index type.
+//! Use `u64` up to base `15`, `u128` up to base `26` and `BigUint` for all bases.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::{successors, tri, BigUint, Counter, Neighborhood, TrinoiseError};
+
+/// Implemented by unsigned integer types used to index nodes.
+pub trait Index: Clone + Ord + fmt::Debug + fmt::Display {
+    /// Converts from `u64`.
+    fn from_u64(v: u64) -> Self;
+    /// Converts to `u64`, returning `None` if the number is too large.
+    fn to_u64(&self) -> Option<u64>;
+    /// Adds two numbers, returning `None` on overflow.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    /// Subtracts a number, returning `None` on underflow.
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    /// Multiplies with a small number, returning `None` on overflow.
+    fn checked_mul_small(&self, m: u32) -> Option<Self>;
+    /// Divides by a small number, returning the quotient and the remainder.
+    fn div_rem_small(&self, d: u32) -> (Self, u32);
+}
+
+macro_rules! primitive_index {
+    ($t:ident) => {
+        impl Index for $t {
+            fn from_u64(v: u64) -> $t {v as $t}
+            fn to_u64(&self) -> Option<u64> {
+                if *self <= u64::MAX as $t {Some(*self as u64)} else {None}
+            }
+            fn checked_add(&self, other: &$t) -> Option<$t> {$t::checked_add(*self, *other)}
+            fn checked_sub(&self, other: &$t) -> Option<$t> {$t::checked_sub(*self, *other)}
+            fn checked_mul_small(&self, m: u32) -> Option<$t> {$t::checked_mul(*self, m as $t)}
+            fn div_rem_small(&self, d: u32) -> ($t, u32) {
+                (*self / d as $t, (*self % d as $t) as u32)
+            }
+        }
+    }
+}
+
+primitive_index!{u64}
+primitive_index!{u128}
+
+impl Index for BigUint {
+    fn from_u64(v: u64) -> BigUint {BigUint::from(v)}
+    fn to_u64(&self) -> Option<u64> {BigUint::to_u64(self)}
+    fn checked_add(&self, other: &BigUint) -> Option<BigUint> {Some(self.add(other))}
+    fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {self.sub(other)}
+    fn checked_mul_small(&self, m: u32) -> Option<BigUint> {Some(self.mul_small(m))}
+    fn div_rem_small(&self, d: u32) -> (BigUint, u32) {BigUint::div_rem_small(self, d)}
+}
+
+/// Converts between index types, returning `None` if the number does not fit.
+fn cast<A: Index, B: Index>(v: &A) -> Option<B> {
+    let zero = A::from_u64(0);
+    let mut v = v.clone();
+    let mut digits = vec![];
+    while v != zero {
+        let (q, d) = v.div_rem_small(1 << 16);
+        digits.push(d);
+        v = q;
+    }
+    let mut r = B::from_u64(0);
+    for &d in digits.iter().rev() {
+        r = r.checked_mul_small(1 << 16)?.checked_add(&B::from_u64(d as u64))?;
+    }
+    Some(r)
+}
+
+/// Computes `base^exp`, returning `None` on overflow.
+pub fn pow<T: Index>(base: u8, exp: u32) -> Option<T> {
+    let mut r = T::from_u64(1);
+    for _ in 0..exp {r = r.checked_mul_small(base as u32)?}
+    Some(r)
+}
+
+/// Returns the number of nodes `n^n`, returning `None` on overflow.
+pub fn end<T: Index>(base: u8) -> Option<T> {pow(base, base as u32)}
+
+/// Counts the number of aligned positions to identity map.
+pub fn aligned<T: Index>(v: &T, base: u8) -> u8 {
+    Counter::from_index(v, base).aligned()
+}
+
+/// Returns the number of successors that share number of aligned positions.
+pub fn next<T: Index>(v: &T, base: u8) -> u8 {
+    successors(Counter::from_index(v, base))
+}
+
+/// Counts the frequencies of `0`, `1` and `2` in the signature of some base.
+///
+/// Returns `None` if the counts do not fit in the index type.
+pub fn frequencies<T: Index>(base: u8) -> Option<[T; 3]> {
+    let zero = T::from_u64(0);
+    let mut p = [zero.clone(), zero.clone(), zero.clone()];
+    if base < 2 {p[0] = T::from_u64(1); return Some(p)}
+    let blocks: T = pow(base, base as u32 - 1)?;
+    let mut joins = zero;
+    for i in 1..base as u32 - 1 {joins = joins.checked_add(&pow(base, i)?)?}
+    let singles = blocks.checked_sub(&joins)?;
+    let a = tri(0, base) as usize;
+    p[a] = p[a].checked_add(&singles)?;
+    let b = tri(base - 2, base) as usize;
+    p[b] = p[b].checked_add(&singles)?;
+    p[tri(base - 1, base) as usize] = joins;
+    Some(p)
+}
+
+// The numbers from `0` to `n^n` are split into blocks of `n` numbers
+// that share all digits except the least significant.
+// Within block `h`, the first `n - 1` numbers form a neighborhood,
+// while the last number has one more aligned position.
+// The last number joins the next block when the next block has one more aligned position,
+// which is when `join(h, base)` is `true`.
+
+/// Returns `true` if the last number of block `h` joins block `h + 1`.
+///
+/// Incrementing `h` changes the first digit that is not `n - 1`.
+/// Carried digits are never aligned, so this is the only digit that can gain alignment.
+fn join<T: Index>(h: &T, base: u8) -> bool {
+    let n = base as u32;
+    let mut h = h.clone();
+    for k in 0..n.saturating_sub(2) {
+        let (q, d) = h.div_rem_small(n);
+        if d != n - 1 {return d + 1 == n - 2 - k}
+        h = q;
+    }
+    false
+}
+
+/// Counts the blocks below `h` which last number joins the next block.
+fn joins_below<T: Index>(h: &T, base: u8) -> Option<T> {
+    let n = base as u32;
+    let mut sum = T::from_u64(0);
+    let mut q = h.clone();
+    for k in 0..n.saturating_sub(2) {
+        let (q2, d) = q.div_rem_small(n);
+        q = q2;
+        sum = sum.checked_add(&q)?;
+        if d + 1 > n - 2 - k {sum = sum.checked_add(&T::from_u64(1))?}
+    }
+    Some(sum)
+}
+
+/// Returns the index in the signature of the first neighborhood of block `h`.
+fn block_index<T: Index>(h: &T, base: u8) -> Option<T> {
+    h.checked_mul_small(2)?.checked_sub(&joins_below(h, base)?)
+}
+
+/// Returns the first neighborhood of block `h`.
+fn block_body<T: Index>(h: &T, base: u8) -> Option<Neighborhood<T>> {
+    let first = h.checked_mul_small(base as u32)?;
+    let joined = match h.checked_sub(&T::from_u64(1)) {
+        Some(g) => join(&g, base),
+        None => false,
+    };
+    let start = if joined {first.checked_sub(&T::from_u64(1))?} else {first.clone()};
+    let len = base as u64 - 1 + joined as u64;
+    Some(Neighborhood {
+        start,
+        len,
+        aligned: aligned(&first, base),
+        tri: tri(len as u8 - 1, base),
+    })
+}
+
+/// Returns the last number of block `h` as a neighborhood of its own.
+fn block_tail<T: Index>(h: &T, base: u8) -> Option<Neighborhood<T>> {
+    let start = h.checked_mul_small(base as u32)?.checked_add(&T::from_u64(base as u64 - 1))?;
+    Some(Neighborhood {aligned: aligned(&start, base), start, len: 1, tri: 0})
+}
+
+/// Returns the neighborhood of the `k`-th value in the signature of some base.
+///
+/// Uses binary search over blocks of `n` numbers,
+/// which takes time polylogarithmic in `n^n`.
+/// Returns `None` if `k` is outside the signature or on overflow.
+pub fn signature_at<T: Index>(base: u8, k: &T) -> Option<Neighborhood<T>> {
+    let zero = T::from_u64(0);
+    let one = T::from_u64(1);
+    if base < 2 {
+        return if *k == zero {
+            Some(Neighborhood {start: zero.clone(), len: 1, aligned: aligned(&zero, base), tri: 0})
+        } else {None};
+    }
+    let blocks: T = match pow(base, base as u32 - 1) {
+        Some(blocks) => blocks,
+        // Search in `BigUint` when the blocks do not fit in the index type.
+        None => {
+            let n = signature_at::<BigUint>(base, &cast(k)?)?;
+            return Some(Neighborhood {start: cast(&n.start)?, len: n.len, aligned: n.aligned, tri: n.tri});
+        }
+    };
+    if *k >= block_index(&blocks, base)? {return None}
+    // Find the last block with first neighborhood at or before `k`.
+    let mut lo = zero;
+    let mut hi = blocks;
+    while hi.checked_sub(&lo)? > one {
+        let mid = lo.checked_add(&hi.checked_sub(&lo)?.div_rem_small(2).0)?;
+        if block_index(&mid, base)? <= *k {lo = mid} else {hi = mid}
+    }
+    if block_index(&lo, base)? == *k {block_body(&lo, base)} else {block_tail(&lo, base)}
+}
+
+/// Returns the neighborhood that contains some number.
+///
+/// The index of the neighborhood in the signature is returned as second value.
+/// Returns `None` if `v` is not less than `n^n` or on overflow.
+pub fn neighborhood_of<T: Index>(v: &T, base: u8) -> Option<(Neighborhood<T>, T)> {
+    match end::<T>(base) {
+        Some(end) if *v >= end => return None,
+        // All numbers of the index type are less than `n^n`.
+        _ => {}
+    }
+    if base < 2 {return Some((signature_at(base, v)?, v.clone()))}
+    let (h, d) = v.div_rem_small(base as u32);
+    if d + 1 < base as u32 {
+        Some((block_body(&h, base)?, block_index(&h, base)?))
+    } else {
+        let h2 = h.checked_add(&T::from_u64(1))?;
+        let last = match pow::<T>(base, base as u32 - 1) {
+            Some(blocks) => h2 >= blocks,
+            None => false,
+        };
+        if !last && join(&h, base) {
+            Some((block_body(&h2, base)?, block_index(&h2, base)?))
+        } else {
+            Some((block_tail(&h, base)?, block_index(&h, base)?.checked_add(&T::from_u64(1))?))
+        }
+    }
+}
+
+// Rank and select are computed in `BigUint`,
+// since intermediate counts can overflow the index type even when the result fits.
+
+/// Counts the ways `m` free positions can have exactly `r` aligned positions.
+///
+/// Each position has one aligned digit and `n - 1` other digits.
+fn ways(base: u8, m: u8, r: u8) -> BigUint {
+    if r > m {return BigUint::zero()}
+    // Binomial coefficient, where each step is an exact division.
+    let mut c = BigUint::from(1);
+    for i in 1..=r as u32 {
+        c = c.mul_small(m as u32 - r as u32 + i).div_rem_small(i).0;
+    }
+    for _ in 0..m - r {c = c.mul_small(base as u32 - 1)}
+    c
+}
+
+/// Counts the numbers below `x` with exactly `a` aligned positions.
+fn count_below(base: u8, a: u8, x: &BigUint) -> BigUint {
+    if *x >= end::<BigUint>(base).unwrap() {return ways(base, base, a)}
+    let c = Counter::from_index(x, base);
+    let mut sum = BigUint::zero();
+    let mut need = a;
+    // Position `i` is compared against digit `i`, starting with the most significant.
+    for (i, &d) in c.digits().iter().rev().enumerate() {
+        let m = base - 1 - i as u8;
+        let t = i as u8;
+        let smaller_aligned = (t < d) as u32;
+        if smaller_aligned == 1 && need > 0 {
+            sum = sum.add(&ways(base, m, need - 1));
+        }
+        sum = sum.add(&ways(base, m, need).mul_small(d as u32 - smaller_aligned));
+        if d == t {
+            if need == 0 {break}
+            need -= 1;
+        }
+    }
+    sum
+}
+
+/// Returns the `k`-th number with exactly `a` aligned positions, or `None` if there is none.
+fn select(base: u8, a: u8, k: &BigUint) -> Option<BigUint> {
+    if *k >= ways(base, base, a) {return None}
+    let mut k = k.clone();
+    let mut v = BigUint::zero();
+    let mut need = a;
+    for i in 0..base {
+        let m = base - 1 - i;
+        let with = if need > 0 {ways(base, m, need - 1)} else {BigUint::zero()};
+        let without = ways(base, m, need);
+        let mut digit = 0;
+        for d in 0..base {
+            let count = if d == i {&with} else {&without};
+            if k < *count {digit = d; break}
+            k = k.sub(count).unwrap();
+        }
+        if digit == i {need -= 1}
+        v = v.mul_small(base as u32).add(&BigUint::from(digit as u64));
+    }
+    Some(v)
+}
+
+/// Counts the numbers within a range that have exactly `a` aligned positions.
+///
+/// Only numbers less than `n^n` are counted.
+/// The count never exceeds the length of the range, so it always fits in the index type.
+pub fn count_aligned<T: Index>(base: u8, a: u8, range: Range<T>) -> T {
+    if range.end <= range.start {return T::from_u64(0)}
+    let hi = count_below(base, a, &cast(&range.end).unwrap());
+    let lo = count_below(base, a, &cast(&range.start).unwrap());
+    cast(&hi.sub(&lo).unwrap()).unwrap()
+}
+
+/// Returns the `k`-th number with exactly `a` aligned positions, counting from zero.
+///
+/// Digits are chosen from the most significant by counting the ways to complete
+/// the remaining positions, so no numbers are scanned.
+/// Returns `Ok(None)` if there are not more than `k` such numbers below `n^n`,
+/// or `TrinoiseError::Overflow` if the number does not fit in the index type.
+pub fn select_aligned<T: Index>(base: u8, a: u8, k: &T) -> Result<Option<T>, TrinoiseError> {
+    match select(base, a, &cast(k).unwrap()) {
+        None => Ok(None),
+        Some(v) => cast(&v).map(Some).ok_or(TrinoiseError::Overflow),
+    }
+}
+
+/// Returns the smallest number greater than `v` with exactly `a` aligned positions.
+///
+/// Returns `Ok(None)` if there is no such number below `n^n`,
+/// or `TrinoiseError::Overflow` if the number does not fit in the index type.
+pub fn successor_with_aligned<T: Index>(base: u8, a: u8, v: &T) -> Result<Option<T>, TrinoiseError> {
+    let v: BigUint = cast(v).unwrap();
+    let below = count_below(base, a, &v.add(&BigUint::from(1)));
+    match select(base, a, &below) {
+        None => Ok(None),
+        Some(v) => cast(&v).map(Some).ok_or(TrinoiseError::Overflow),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic() {
+        for base in 1..6 {
+            let end = (base as u64).pow(base as u32);
+            for v in 0..end {
+                let a = crate::aligned(v, base);
+                assert_eq!(aligned(&v, base), a);
+                assert_eq!(aligned(&(v as u128), base), a);
+                assert_eq!(aligned(&BigUint::from(v), base), a);
+                if base < 2 {continue}
+                let n = crate::next(v, base);
+                assert_eq!(next(&(v as u128), base), n);
+                assert_eq!(next(&BigUint::from(v), base), n);
+            }
+        }
+
+        // The identity map is aligned at every position.
+        for &base in &[20, 50, 255] {
+            let mut id = BigUint::zero();
+            for i in 0..base {id = id.mul_small(base as u32).add(&BigUint::from(i as u64))}
+            assert_eq!(aligned(&id, base), base);
+            assert_eq!(next(&id, base), 0);
+            assert_eq!(next(&BigUint::zero(), base), base - 2);
+        }
+        assert!(end::<u64>(15).is_some());
+        assert!(end::<u64>(16).is_none());
+        assert!(end::<u128>(26).is_some());
+        assert!(end::<u128>(27).is_none());
+
+        let n = 50;
+        let mut k = BigUint::zero();
+        for _ in 0..n {k = k.mul_small(n as u32).add(&BigUint::from(1))}
+        let (nb, i) = neighborhood_of(&k, n).unwrap();
+        assert_eq!(signature_at(n, &i), Some(nb.clone()));
+        assert!(nb.start <= k && k < nb.start.add(&BigUint::from(nb.len)));
+
+        let n = 255;
+        let k = frequencies::<BigUint>(n).unwrap()[0].clone();
+        let nb = signature_at(n, &k).unwrap();
+        assert_eq!(neighborhood_of(&nb.start, n), Some((nb, k)));
+
+        let n = 40;
+        let a = 7;
+        let k = BigUint::from(123_456_789);
+        let v = select_aligned(n, a, &k).unwrap().unwrap();
+        assert_eq!(aligned(&v, n), a);
+        assert_eq!(count_aligned(n, a, BigUint::zero()..v.clone()), k);
+        let w = successor_with_aligned(n, a, &v).unwrap().unwrap();
+        assert_eq!(aligned(&w, n), a);
+        assert_eq!(count_aligned(n, a, v..w), BigUint::from(1));
+
+        assert_eq!(frequencies::<u64>(5), Some([470, 470, 155]));
+        assert_eq!(frequencies::<u64>(18), None);
+        let n = 30;
+        let p = frequencies::<BigUint>(n).unwrap();
+        assert_eq!(p[0], p[1]);
+        // `p2 * (n - 1) + n = n^(n-1)`.
+        assert_eq!(p[2].mul_small(n as u32 - 1).add(&BigUint::from(n as u64)),
+                   pow(n, n as u32 - 1).unwrap());
+    }
+}
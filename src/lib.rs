@@ -173,12 +173,48 @@
 //!
 //! A signature is a fragmentation of the skewed Hamming N-Sphere
 //! onto the sequence of natural numbers.
-//! It is not possible to enumerate all points of equal Hamming distance directly,
-//! but requires checking the aligned positions of the first number.
+//! The signature itself does not enumerate all points of equal Hamming distance directly,
+//! since neighborhoods of the same distance are scattered along the sequence.
+//! Instead, these points can be counted and enumerated by combinatorics of digit positions
+//! using `count_aligned`, `select_aligned` and `successor_with_aligned`,
+//! without checking the aligned positions of every number.
 //!
 //! Therefore, trinoise signatures have no direct intuitive geometric interpretation.
 //! Although, it might happen that this application of geometry is useful for Number Theory.
 
+use std::ops::Range;
+
+pub use base::Base;
+pub use checkpoint::{Checkpoint, Job};
+pub use big::BigUint;
+pub use counter::Counter;
+pub use depth::DepthOrder;
+pub use error::TrinoiseError;
+pub use index::Index;
+pub use node::{Modification, Node};
+pub use parallel::parallel_signature;
+pub use reference::{size_tri, Reference, ReferenceNeighborhoods};
+
+mod base;
+mod big;
+mod checkpoint;
+mod counter;
+mod depth;
+mod error;
+pub mod image;
+pub mod index;
+mod node;
+pub mod noise;
+mod parallel;
+mod reference;
+pub mod search;
+pub mod sets;
+pub mod spectrum;
+pub mod stats;
+pub mod tree;
+pub mod trn;
+pub mod verify;
+
 /// Counts the number of aligned positions to identity map.
 ///
 /// The maximum number of aligned positions is equal to the base.
@@ -195,18 +231,31 @@ pub fn aligned(mut v: u64, base: u8) -> u8 {
     sum
 }
 
+/// Returns the depth of a node in the reachability tree with identity map as root.
+///
+/// This is the smallest number of modifications from the identity map,
+/// equal to `base` minus aligned positions.
+pub fn depth(v: u64, base: u8) -> u8 {
+    base - aligned(v, base)
+}
+
 /// Returns the number of successors that share number of aligned positions.
 ///
 /// This is always a number `0`, `base - 2` or `base - 1`.
 ///
 /// This number can also be used to increase the counter, hence the name `next`,
 /// even if the returned value is strictly an offset.
-pub fn next(mut v: u64, base: u8) -> u8 {
+pub fn next(v: u64, base: u8) -> u8 {
+    successors(Counter::new(v, base))
+}
+
+/// Counts the successors of a counter that share number of aligned positions.
+fn successors(mut c: Counter) -> u8 {
     let mut sum = 0;
-    let a = aligned(v, base);
+    let a = c.aligned();
     loop {
-        let b = aligned(v + 1, base);
-        if a == b {sum += 1; v += 1} else {break}
+        c.increment();
+        if a == c.aligned() {sum += 1} else {break}
     }
     sum
 }
@@ -217,21 +266,281 @@ pub fn tri(c: u8, base: u8) -> u8 {
 }
 
 /// Calculates signature of successors with shared aligned positions.
+///
+/// Panics if `n^n` does not fit in `u64`, see `checked_signature`.
 pub fn signature(base: u8) -> Vec<u8> {
-    let end = (base as u64).pow(base as u32);
-    let mut v = 0;
-    let mut r = vec![];
-    // Do not include the end since it would wrap count successors.
-    while v + 1 < end {
-        let n = next(v, base);
-        v += n as u64 + 1;
-        r.push(tri(n, base));
+    SignatureIter::from_base(base).collect()
+}
+
+/// Checks that a number is less than `n^n`.
+fn check_range(v: u64, base: Base) -> Result<(), TrinoiseError> {
+    match base.end() {
+        Ok(end) if v >= end => Err(TrinoiseError::IndexOutOfRange),
+        // All `u64` numbers are less than `n^n`.
+        _ => Ok(())
     }
+}
+
+/// Counts the number of aligned positions to identity map.
+///
+/// Returns `TrinoiseError::IndexOutOfRange` if `v` is not less than `n^n`.
+pub fn checked_aligned(v: u64, base: Base) -> Result<u8, TrinoiseError> {
+    check_range(v, base)?;
+    Ok(aligned(v, base.get()))
+}
+
+/// Returns the number of successors that share number of aligned positions.
+///
+/// Unlike `next`, this does not wrap around to count successors after `n^n - 1`.
+///
+/// Returns `TrinoiseError::IndexOutOfRange` if `v` is not less than `n^n`.
+pub fn checked_next(v: u64, base: Base) -> Result<u8, TrinoiseError> {
+    check_range(v, base)?;
     // The end always has no successors.
-    r.push(0);
-    r
+    // Compare in `u128`, since the last node of base `16` is `u64::MAX`.
+    if index::end::<u128>(base.get()) == Some(v as u128 + 1) {return Ok(0)}
+    Ok(next(v, base.get()))
+}
+
+/// Maps `0 => 0, base - 2 => 1, base - 1 => 2`.
+///
+/// Returns `TrinoiseError::IndexOutOfRange` if `c` is not `0`, `base - 2` or `base - 1`,
+/// since `next` never returns other offsets.
+pub fn checked_tri(c: u8, base: Base) -> Result<u8, TrinoiseError> {
+    let n = base.get();
+    if c != 0 && c != n - 2 && c != n - 1 {return Err(TrinoiseError::IndexOutOfRange)}
+    Ok(tri(c, n))
+}
+
+/// Calculates signature of successors with shared aligned positions.
+///
+/// Returns `TrinoiseError::Overflow` if `n^n` does not fit in `u64`.
+pub fn checked_signature(base: Base) -> Result<Vec<u8>, TrinoiseError> {
+    base.end()?;
+    Ok(signature(base.get()))
+}
+
+/// Counts the frequencies of `0`, `1` and `2` in the signature of some base.
+///
+/// This is computed exactly without enumerating the `n^n` numbers.
+/// Every block of `n` numbers sharing all digits except the least significant
+/// contributes a neighborhood of `n - 1` or `n` numbers (`1` and `2`),
+/// plus a neighborhood of a single number (`0`) unless it joins the next block.
+/// Joins happen `n^(n-2) + n^(n-3) + ... + n` times.
+///
+/// The counts fit in `u128` for bases up to `27`.
+/// Use `index::frequencies` with `BigUint` for larger bases.
+pub fn frequencies(base: u8) -> [u128; 3] {
+    index::frequencies(base).expect("Frequencies overflow `u128`")
+}
+
+/// Counts the number of values in the signature of some base.
+///
+/// Returns `None` if the length does not fit in `u64`.
+fn signature_len(base: u8) -> Option<u64> {
+    let p = index::frequencies::<u64>(base)?;
+    p[0].checked_add(p[1])?.checked_add(p[2])
+}
+
+/// Returns the neighborhood of the `k`-th value in the signature of some base.
+///
+/// This is computed without enumerating the signature up to `k`.
+/// Returns `None` if `k` is outside the signature.
+pub fn signature_at(base: u8, k: u64) -> Option<Neighborhood> {
+    index::signature_at(base, &k)
+}
+
+/// Returns the neighborhood that contains some number.
+///
+/// The index of the neighborhood in the signature is returned as second value.
+/// Returns `None` if `v` is not less than `n^n`.
+pub fn neighborhood_of(v: u64, base: u8) -> Option<(Neighborhood, u64)> {
+    index::neighborhood_of(&v, base)
+}
+
+/// Counts the numbers within a range that have exactly `a` aligned positions.
+///
+/// Only numbers less than `n^n` are counted.
+/// This uses combinatorics of digit positions instead of scanning the range.
+pub fn count_aligned(base: u8, a: u8, range: Range<u64>) -> u64 {
+    index::count_aligned(base, a, range)
+}
+
+/// Returns the `k`-th number with exactly `a` aligned positions, counting from zero.
+///
+/// Returns `Ok(None)` if there are not more than `k` such numbers below `n^n`,
+/// or `TrinoiseError::Overflow` if the number does not fit in `u64`.
+pub fn select_aligned(base: u8, a: u8, k: u64) -> Result<Option<u64>, TrinoiseError> {
+    index::select_aligned(base, a, &k)
+}
+
+/// Returns the smallest number greater than `v` with exactly `a` aligned positions.
+///
+/// Returns `Ok(None)` if there is no such number below `n^n`,
+/// or `TrinoiseError::Overflow` if the number does not fit in `u64`.
+pub fn successor_with_aligned(base: u8, a: u8, v: u64) -> Result<Option<u64>, TrinoiseError> {
+    index::successor_with_aligned(base, a, &v)
+}
+
+/// Lazy signature of successors with shared aligned positions.
+///
+/// Produces the same values as `signature`, but one by one with constant memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    base: Base,
+}
+
+impl Signature {
+    /// Creates a new signature of some base.
+    ///
+    /// Returns `TrinoiseError::Overflow` if `n^n` does not fit in `u64`,
+    /// such that the length of the signature is always known.
+    pub fn new(base: Base) -> Result<Signature, TrinoiseError> {
+        base.end()?;
+        Ok(Signature {base})
+    }
+
+    /// Returns the base of numbers.
+    pub fn base(&self) -> Base {self.base}
+
+    /// Returns an iterator over the values of the signature.
+    pub fn iter(&self) -> SignatureIter {
+        SignatureIter::from_base(self.base.get())
+    }
+}
+
+impl IntoIterator for Signature {
+    type Item = u8;
+    type IntoIter = SignatureIter;
+    fn into_iter(self) -> SignatureIter {self.iter()}
+}
+
+/// Iterates over the values of a signature.
+#[derive(Clone, Debug)]
+pub struct SignatureIter {
+    neighborhoods: Neighborhoods,
+}
+
+impl SignatureIter {
+    /// Creates a new signature iterator of some base.
+    ///
+    /// Returns `TrinoiseError::Overflow` if `n^n` does not fit in `u64`.
+    pub fn new(base: Base) -> Result<SignatureIter, TrinoiseError> {
+        Ok(SignatureIter {neighborhoods: Neighborhoods::new(base)?})
+    }
+
+    /// Creates a new signature iterator of any base, including `0` and `1`.
+    ///
+    /// Panics if `n^n` does not fit in `u64`.
+    pub(crate) fn from_base(base: u8) -> SignatureIter {
+        SignatureIter {neighborhoods: Neighborhoods::from_base(base)}
+    }
 }
 
+impl Iterator for SignatureIter {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        self.neighborhoods.next().map(|n| n.tri)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.neighborhoods.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SignatureIter {}
+
+impl std::iter::FusedIterator for SignatureIter {}
+
+/// A local neighborhood of numbers in incrementing sequence with same amount of aligned positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Neighborhood<T = u64> {
+    /// The first number in the neighborhood.
+    pub start: T,
+    /// The number of members in the neighborhood.
+    pub len: u64,
+    /// The aligned positions shared by all members.
+    pub aligned: u8,
+    /// The signature value of the neighborhood.
+    pub tri: u8,
+}
+
+/// Iterates over the local neighborhoods from `0` to `n^n`.
+#[derive(Clone, Debug)]
+pub struct Neighborhoods {
+    counter: Counter,
+    v: u64,
+    end: u64,
+    left: u64,
+}
+
+impl Neighborhoods {
+    /// Creates a new neighborhood iterator of some base.
+    ///
+    /// Returns `TrinoiseError::Overflow` if `n^n` does not fit in `u64`.
+    pub fn new(base: Base) -> Result<Neighborhoods, TrinoiseError> {
+        base.end()?;
+        Ok(Neighborhoods::from_base(base.get()))
+    }
+
+    /// Creates a new neighborhood iterator of any base, including `0` and `1`.
+    ///
+    /// Panics if `n^n` does not fit in `u64`.
+    pub(crate) fn from_base(base: u8) -> Neighborhoods {
+        let end = index::end::<u64>(base).expect("`n^n` overflows `u64`");
+        Neighborhoods {
+            counter: Counter::new(0, base),
+            v: 0,
+            end,
+            // The signature is never longer than `n^n`.
+            left: signature_len(base).unwrap(),
+        }
+    }
+}
+
+impl Iterator for Neighborhoods {
+    type Item = Neighborhood;
+    fn next(&mut self) -> Option<Neighborhood> {
+        if self.left == 0 {return None}
+        self.left -= 1;
+        let start = self.v;
+        let a = self.counter.aligned();
+        let len = neighborhood_len(&mut self.counter, start, self.end);
+        self.v += len;
+        Some(Neighborhood {
+            start,
+            len,
+            aligned: a,
+            tri: tri((len - 1) as u8, self.counter.base()),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.left as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for Neighborhoods {}
+
+/// Counts the members of the neighborhood starting at the counter.
+///
+/// Moves the counter to the start of the next neighborhood.
+fn neighborhood_len(counter: &mut Counter, start: u64, end: u64) -> u64 {
+    let a = counter.aligned();
+    let mut len = 0;
+    loop {
+        len += 1;
+        counter.increment();
+        // Do not include the end since it would wrap count successors.
+        // The end always has no successors.
+        if start + len == end || counter.aligned() != a {break}
+    }
+    len
+}
+
+impl std::iter::FusedIterator for Neighborhoods {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,9 +576,160 @@ mod tests {
             p[s[i] as usize] += 1;
         }
         assert_eq!(p, [6, 6, 3]);                   // 3
-        // assert_eq!(p, [44, 44, 20]);             // 4
-        // assert_eq!(p, [470, 470, 155]);          // 5
-        // assert_eq!(p, [6222, 6222, 1554]);       // 6
-        // assert_eq!(p, [98042, 98042, 19607]);    // 7
+        assert_eq!(frequencies(3), [6, 6, 3]);
+        assert_eq!(frequencies(4), [44, 44, 20]);
+        assert_eq!(frequencies(5), [470, 470, 155]);
+        assert_eq!(frequencies(6), [6222, 6222, 1554]);
+        assert_eq!(frequencies(7), [98042, 98042, 19607]);
+    }
+
+    #[test]
+    fn frequencies_enumerated() {
+        for base in 0..8 {
+            let mut p = [0, 0, 0];
+            for x in signature(base) {p[x as usize] += 1}
+            assert_eq!(p, frequencies(base));
+        }
+        assert_eq!(frequencies(27)[2], (27u128.pow(26) - 27) / 26);
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(Base::new(0), Err(TrinoiseError::BaseTooSmall(0)));
+        assert_eq!(Base::new(1), Err(TrinoiseError::BaseTooSmall(1)));
+        let base = Base::new(3).unwrap();
+        assert_eq!(checked_aligned(5, base), Ok(3));
+        assert_eq!(checked_aligned(27, base), Err(TrinoiseError::IndexOutOfRange));
+        assert_eq!(checked_next(2, base), Ok(2));
+        assert_eq!(checked_next(26, base), Ok(0));
+        assert_eq!(checked_next(27, base), Err(TrinoiseError::IndexOutOfRange));
+        assert_eq!(checked_tri(1, base), Ok(1));
+        assert_eq!(checked_tri(3, base), Err(TrinoiseError::IndexOutOfRange));
+        let six = Base::new(6).unwrap();
+        assert_eq!([0, 4, 5].map(|c| checked_tri(c, six)), [Ok(0), Ok(1), Ok(2)]);
+        for c in (1..4).chain(6..9) {
+            assert_eq!(checked_tri(c, six), Err(TrinoiseError::IndexOutOfRange));
+        }
+        let two = Base::new(2).unwrap();
+        assert_eq!([0, 1].map(|c| checked_tri(c, two)), [Ok(0), Ok(2)]);
+        assert_eq!(checked_tri(2, two), Err(TrinoiseError::IndexOutOfRange));
+        assert_eq!(checked_signature(base), Ok(signature(3)));
+
+        let base = Base::new(16).unwrap();
+        assert_eq!(checked_signature(base), Err(TrinoiseError::Overflow));
+        assert_eq!(checked_aligned(u64::MAX, base), Ok(aligned(u64::MAX, 16)));
+        assert_eq!(next(u64::MAX, 16), 15);
+        assert_eq!(checked_next(u64::MAX, base), Ok(0));
+        assert_eq!(checked_next(u64::MAX - 1, base), Ok(next(u64::MAX - 1, 16)));
+    }
+
+    #[test]
+    fn signature_iter() {
+        for base in 0..7 {
+            let mut v = 0;
+            let end = (base as u64).pow(base as u32);
+            let mut r = vec![];
+            while v + 1 < end {
+                let n = next(v, base);
+                v += n as u64 + 1;
+                r.push(tri(n, base));
+            }
+            r.push(0);
+
+            assert_eq!(signature(base), r);
+            if base < 2 {continue}
+            let iter = Signature::new(Base::new(base).unwrap()).unwrap().iter();
+            assert_eq!(iter.len(), r.len());
+            assert_eq!(iter.collect::<Vec<u8>>(), r);
+        }
+
+        // The length of the signature does not fit in `u64`.
+        let base = Base::new(16).unwrap();
+        assert_eq!(Signature::new(base), Err(TrinoiseError::Overflow));
+        assert!(SignatureIter::new(base).is_err());
+        assert!(Neighborhoods::new(base).is_err());
+        assert_eq!(signature_len(17), None);
+
+        let mut iter = Signature::new(Base::new(3).unwrap()).unwrap().into_iter().skip(14);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn random_access() {
+        for base in 0..7 {
+            let mut k = 0;
+            for n in Neighborhoods::from_base(base) {
+                assert_eq!(signature_at(base, k), Some(n));
+                for v in n.start..n.start + n.len {
+                    assert_eq!(neighborhood_of(v, base), Some((n, k)));
+                }
+                k += 1;
+            }
+            assert_eq!(signature_at(base, k), None);
+            assert_eq!(neighborhood_of((base as u64).pow(base as u32), base), None);
+        }
+
+        // Every `u64` is a node when `n^n` does not fit in `u64`.
+        for base in 16..18 {
+            for &v in &[5, 1 << 40, u64::MAX] {
+                let (n, k) = neighborhood_of(v, base).unwrap();
+                assert_eq!(signature_at(base, k), Some(n));
+                assert!(n.start <= v && v - n.start < n.len);
+                let (n2, k2) = index::neighborhood_of(&(v as u128), base).unwrap();
+                assert_eq!((n2.start, n2.len, k2), (n.start as u128, n.len, k as u128));
+            }
+        }
+    }
+
+    #[test]
+    fn rank_select() {
+        for base in 1..6 {
+            let end = (base as u64).pow(base as u32);
+            for a in 0..=base {
+                let all: Vec<u64> = (0..end).filter(|&v| aligned(v, base) == a).collect();
+                assert_eq!(count_aligned(base, a, 0..end), all.len() as u64);
+                assert_eq!(count_aligned(base, a, 3..end + 5),
+                           all.iter().filter(|&&v| v >= 3).count() as u64);
+                for (k, &v) in all.iter().enumerate() {
+                    assert_eq!(select_aligned(base, a, k as u64), Ok(Some(v)));
+                    assert_eq!(count_aligned(base, a, 0..v), k as u64);
+                    assert_eq!(successor_with_aligned(base, a, v), Ok(all.get(k + 1).cloned()));
+                }
+                assert_eq!(select_aligned(base, a, all.len() as u64), Ok(None));
+            }
+        }
+
+        // Intermediate counts overflow `u64` for large bases.
+        let base = 20;
+        let all: Vec<u64> = (0..1000).filter(|&v| aligned(v, base) == 1).collect();
+        assert_eq!(count_aligned(base, 1, 0..1000), all.len() as u64);
+        assert_eq!(select_aligned(base, 1, 0), Ok(Some(all[0])));
+        assert_eq!(successor_with_aligned(base, 1, all[2]), Ok(Some(all[3])));
+        // The identity map of base `20` does not fit in `u64`.
+        assert_eq!(select_aligned(base, base, 0), Err(TrinoiseError::Overflow));
+        assert_eq!(successor_with_aligned(base, base, 0), Err(TrinoiseError::Overflow));
+    }
+
+    #[test]
+    fn neighborhoods() {
+        let base = 3;
+        let mut iter = Neighborhoods::new(Base::new(base).unwrap()).unwrap();
+        assert_eq!(iter.next(), Some(Neighborhood {start: 0, len: 2, aligned: 1, tri: 1}));
+        assert_eq!(iter.next(), Some(Neighborhood {start: 2, len: 3, aligned: 2, tri: 2}));
+        assert_eq!(iter.next(), Some(Neighborhood {start: 5, len: 1, aligned: 3, tri: 0}));
+
+        for base in 2..7 {
+            let end = (base as u64).pow(base as u32);
+            let mut v = 0;
+            for n in Neighborhoods::new(Base::new(base).unwrap()).unwrap() {
+                assert_eq!(n.start, v);
+                for i in 0..n.len {assert_eq!(aligned(v + i, base), n.aligned)}
+                if n.start > 0 {assert_ne!(aligned(v - 1, base), n.aligned)}
+                v += n.len;
+            }
+            assert_eq!(v, end);
+        }
     }
 }
@@ -0,0 +1,270 @@
+//! Nodes of the groupoid as endofunctions.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{index, Counter, Index, TrinoiseError};
+
+/// A node in the groupoid, which is an endofunction of `[n]`.
+///
+/// The node is stored as the list of positions `[f(0), f(1), ..., f(n-1)]`.
+/// In the numeric encoding used by `aligned` and `next`,
+/// `f(0)` is the most significant digit in base `n`,
+/// such that the identity map `012` in base `3` is the number `5`.
+///
+/// Nodes are written in base `n` notation, e.g. `012`.
+/// For bases larger than `36`, positions are written as decimal numbers separated by `.`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Node {
+    positions: Vec<u8>,
+}
+
+impl Node {
+    /// Creates a new node from a list of positions.
+    ///
+    /// The base is the length of the list.
+    /// Returns `TrinoiseError::IndexOutOfRange` if some value is not less than the base,
+    /// or `TrinoiseError::Overflow` if the base is larger than `255`.
+    pub fn new(positions: Vec<u8>) -> Result<Node, TrinoiseError> {
+        if positions.len() > u8::MAX as usize {return Err(TrinoiseError::Overflow)}
+        if positions.iter().any(|&x| x as usize >= positions.len()) {
+            return Err(TrinoiseError::IndexOutOfRange);
+        }
+        Ok(Node {positions})
+    }
+
+    /// Returns the identity map `[0, 1, ..., n-1]`.
+    pub fn identity(base: u8) -> Node {
+        Node {positions: (0..base).collect()}
+    }
+
+    /// Converts from the numeric encoding.
+    ///
+    /// Returns `TrinoiseError::IndexOutOfRange` if `v` is not less than `n^n`.
+    pub fn from_u64(v: u64, base: u8) -> Result<Node, TrinoiseError> {
+        Node::from_index(&v, base)
+    }
+
+    /// Converts from the numeric encoding of any index type.
+    ///
+    /// Returns `TrinoiseError::IndexOutOfRange` if `v` is not less than `n^n`.
+    pub fn from_index<T: Index>(v: &T, base: u8) -> Result<Node, TrinoiseError> {
+        match index::end::<T>(base) {
+            Some(end) if *v >= end => return Err(TrinoiseError::IndexOutOfRange),
+            // All numbers of the index type are less than `n^n`.
+            _ => {}
+        }
+        let c = Counter::from_index(v, base);
+        Ok(Node {positions: c.digits().iter().rev().cloned().collect()})
+    }
+
+    /// Converts to the numeric encoding.
+    ///
+    /// Returns `None` if the number does not fit in `u64`.
+    pub fn to_u64(&self) -> Option<u64> {self.to_index()}
+
+    /// Converts to the numeric encoding of any index type.
+    ///
+    /// Returns `None` if the number does not fit in the index type.
+    pub fn to_index<T: Index>(&self) -> Option<T> {
+        let base = self.base() as u32;
+        let mut v = T::from_u64(0);
+        for &x in &self.positions {
+            v = v.checked_mul_small(base)?.checked_add(&T::from_u64(x as u64))?;
+        }
+        Some(v)
+    }
+
+    /// Returns the base of the node.
+    pub fn base(&self) -> u8 {self.positions.len() as u8}
+
+    /// Returns the list of positions `[f(0), f(1), ..., f(n-1)]`.
+    pub fn positions(&self) -> &[u8] {&self.positions}
+
+    /// Returns `f(i)`.
+    pub fn get(&self, i: u8) -> u8 {self.positions[i as usize]}
+
+    /// Counts the number of aligned positions to identity map.
+    pub fn aligned(&self) -> u8 {
+        self.positions.iter().enumerate().filter(|&(i, &x)| i == x as usize).count() as u8
+    }
+
+    /// Returns the depth in the reachability tree with identity map as root.
+    pub fn depth(&self) -> u8 {self.base() - self.aligned()}
+
+    /// Returns `true` if the node is a permutation.
+    pub fn is_permutation(&self) -> bool {
+        let mut seen = vec![false; self.positions.len()];
+        for &x in &self.positions {
+            if seen[x as usize] {return false}
+            seen[x as usize] = true;
+        }
+        true
+    }
+
+    /// Returns a new node where `f(pos)` is replaced by `value`.
+    ///
+    /// Returns `TrinoiseError::IndexOutOfRange` if `pos` or `value` is not less than the base.
+    pub fn modify(&self, pos: u8, value: u8) -> Result<Node, TrinoiseError> {
+        if pos >= self.base() || value >= self.base() {
+            return Err(TrinoiseError::IndexOutOfRange);
+        }
+        let mut positions = self.positions.clone();
+        positions[pos as usize] = value;
+        Ok(Node {positions})
+    }
+
+    /// Applies a sequence of modifications.
+    pub fn apply(&self, mods: &[Modification]) -> Result<Node, TrinoiseError> {
+        mods.iter().try_fold(self.clone(), |n, m| n.modify(m.pos, m.value))
+    }
+
+    /// Returns the number of positions where two nodes differ.
+    ///
+    /// Returns `None` if the nodes have different bases.
+    pub fn distance(&self, other: &Node) -> Option<u8> {
+        if self.base() != other.base() {return None}
+        Some(self.positions.iter().zip(&other.positions).filter(|(a, b)| a != b).count() as u8)
+    }
+
+    /// Returns a shortest sequence of modifications that transforms this node into another.
+    ///
+    /// The length of the path is the Hamming distance between the nodes,
+    /// since each modification changes a single position.
+    /// Positions are modified in increasing order.
+    /// Returns `None` if the nodes have different bases.
+    pub fn path_to(&self, other: &Node) -> Option<Vec<Modification>> {
+        if self.base() != other.base() {return None}
+        Some(self.positions.iter().zip(&other.positions).enumerate()
+            .filter(|&(_, (a, b))| a != b)
+            .map(|(i, (_, &b))| Modification {pos: i as u8, value: b})
+            .collect())
+    }
+
+    /// Returns a shortest sequence of modifications from the identity map to this node.
+    ///
+    /// The length of the path is `base - aligned`, which is the depth of the node.
+    /// Each modification increases the depth by one.
+    pub fn path_from_identity(&self) -> Vec<Modification> {
+        Node::identity(self.base()).path_to(self).unwrap()
+    }
+}
+
+/// A modification of a single position of a node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Modification {
+    /// The position to modify.
+    pub pos: u8,
+    /// The new value at the position.
+    pub value: u8,
+}
+
+impl fmt::Display for Modification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "f({}) = {}", self.pos, self.value)
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.base() <= 36 {
+            for &x in &self.positions {
+                write!(f, "{}", std::char::from_digit(x as u32, 36).unwrap())?;
+            }
+        } else {
+            for (i, &x) in self.positions.iter().enumerate() {
+                if i > 0 {write!(f, ".")?}
+                write!(f, "{}", x)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Node {
+    type Err = TrinoiseError;
+    fn from_str(s: &str) -> Result<Node, TrinoiseError> {
+        let positions = if s.contains('.') {
+            s.split('.').map(|w| {
+                w.parse::<u8>().map_err(|_| {
+                    TrinoiseError::InvalidDigit(w.chars().find(|c| !c.is_ascii_digit()).unwrap_or('.'))
+                })
+            }).collect::<Result<Vec<u8>, _>>()?
+        } else {
+            s.chars().map(|ch| {
+                ch.to_digit(36).map(|d| d as u8).ok_or(TrinoiseError::InvalidDigit(ch))
+            }).collect::<Result<Vec<u8>, _>>()?
+        };
+        Node::new(positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aligned, BigUint};
+
+    #[test]
+    fn node() {
+        for base in 1..6 {
+            let end = (base as u64).pow(base as u32);
+            for v in 0..end {
+                let n = Node::from_u64(v, base).unwrap();
+                assert_eq!(n.base(), base);
+                assert_eq!(n.to_u64(), Some(v));
+                assert_eq!(n.aligned(), aligned(v, base));
+                assert_eq!(n.to_string().parse::<Node>(), Ok(n));
+            }
+            assert_eq!(Node::from_u64(end, base), Err(TrinoiseError::IndexOutOfRange));
+        }
+
+        let n: Node = "012".parse().unwrap();
+        assert_eq!(n, Node::identity(3));
+        assert_eq!(n.to_u64(), Some(5));
+        assert_eq!(n.aligned(), 3);
+        assert!(n.is_permutation() && !"010".parse::<Node>().unwrap().is_permutation());
+        assert_eq!(Node::from_u64(21, 3).unwrap().positions(), &[2, 1, 0]);
+        assert_eq!(Node::from_u64(21, 3).unwrap().depth(), 2);
+        assert_eq!("0a1".parse::<Node>(), Err(TrinoiseError::IndexOutOfRange));
+        assert_eq!("0_1".parse::<Node>(), Err(TrinoiseError::InvalidDigit('_')));
+
+        let n = Node::identity(40);
+        assert!(n.to_string().starts_with("0.1.2."));
+        assert_eq!(n.to_string().parse::<Node>(), Ok(n.clone()));
+        assert_eq!(n.to_u64(), None);
+        let v: BigUint = n.to_index().unwrap();
+        assert_eq!(Node::from_index(&v, 40), Ok(n));
+    }
+
+    #[test]
+    fn modification() {
+        let id = Node::identity(3);
+        let n = id.modify(0, 2).unwrap();
+        assert_eq!(n.to_string(), "212");
+        assert_eq!(id.modify(3, 0), Err(TrinoiseError::IndexOutOfRange));
+        assert_eq!(id.modify(0, 3), Err(TrinoiseError::IndexOutOfRange));
+        assert_eq!(id.distance(&Node::identity(2)), None);
+
+        for base in 1..5 {
+            let end = (base as u64).pow(base as u32);
+            let id = Node::identity(base);
+            for v in 0..end {
+                let a = Node::from_u64(v, base).unwrap();
+                let path = a.path_from_identity();
+                assert_eq!(path.len() as u8, base - aligned(v, base));
+                let mut m = id.clone();
+                for (i, x) in path.iter().enumerate() {
+                    m = m.modify(x.pos, x.value).unwrap();
+                    assert_eq!(m.depth() as usize, i + 1);
+                }
+                assert_eq!(m, a);
+                for w in 0..end {
+                    let b = Node::from_u64(w, base).unwrap();
+                    let path = a.path_to(&b).unwrap();
+                    assert_eq!(path.len() as u8, a.distance(&b).unwrap());
+                    assert_eq!(a.apply(&path), Ok(b));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! 2D and 3D noise samplers for procedural generation.
+//!
+//! Every natural number `v` gets the value of the neighborhood that contains `v mod N^N`,
+//! which repeats after `N^N` for base `N`.
+//! Integer coordinates are mapped to natural numbers with a pairing function,
+//! while floating-point coordinates interpolate between lattice points (value noise).
+
+use crate::{index, Base, TrinoiseError};
+
+/// Maps integers to natural numbers `0, -1, 1, -2, 2, ... => 0, 1, 2, 3, 4, ...`.
+fn zigzag(x: i64) -> u64 {((x << 1) ^ (x >> 63)) as u64}
+
+/// Szudzik's pairing function, mapping two natural numbers to one.
+fn pair(a: u64, b: u64) -> u128 {
+    let (a, b) = (a as u128, b as u128);
+    if a >= b {a * a + a + b} else {a + b * b}
+}
+
+/// Smooth interpolation factor.
+fn smooth(t: f64) -> f64 {t * t * (3.0 - 2.0 * t)}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {a + (b - a) * t}
+
+/// Samples the noise value of a natural number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Lattice {
+    base: u8,
+    period: u64,
+}
+
+impl Lattice {
+    fn new(base: Base) -> Result<Lattice, TrinoiseError> {
+        Ok(Lattice {base: base.get(), period: base.end()?})
+    }
+
+    fn get(&self, v: u128) -> u8 {
+        let v = (v % self.period as u128) as u64;
+        index::neighborhood_of(&v, self.base).unwrap().0.tri
+    }
+}
+
+/// Samples trinoise on a 2D lattice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Noise2D {
+    lattice: Lattice,
+}
+
+impl Noise2D {
+    /// Creates a new 2D sampler of some base.
+    ///
+    /// Returns `TrinoiseError::Overflow` if `N^N` does not fit in `u64`.
+    pub fn new(base: Base) -> Result<Noise2D, TrinoiseError> {
+        Ok(Noise2D {lattice: Lattice::new(base)?})
+    }
+
+    /// Returns the value `0`, `1` or `2` at a lattice point.
+    pub fn get(&self, x: i64, y: i64) -> u8 {
+        self.lattice.get(pair(zigzag(x), zigzag(y)))
+    }
+
+    /// Samples the noise in the range `[0, 1]`, interpolating between lattice points.
+    pub fn sample(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+        // Coordinates saturate when converted and wrap around at the edges of `i64`.
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let v = |dx, dy| self.get(x0.wrapping_add(dx), y0.wrapping_add(dy)) as f64 / 2.0;
+        lerp(lerp(v(0, 0), v(1, 0), tx), lerp(v(0, 1), v(1, 1), tx), ty)
+    }
+}
+
+/// Samples trinoise on a 3D lattice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Noise3D {
+    lattice: Lattice,
+}
+
+impl Noise3D {
+    /// Creates a new 3D sampler of some base.
+    ///
+    /// Returns `TrinoiseError::Overflow` if `N^N` does not fit in `u64`.
+    pub fn new(base: Base) -> Result<Noise3D, TrinoiseError> {
+        Ok(Noise3D {lattice: Lattice::new(base)?})
+    }
+
+    /// Returns the value `0`, `1` or `2` at a lattice point.
+    pub fn get(&self, x: i64, y: i64, z: i64) -> u8 {
+        let xy = (pair(zigzag(x), zigzag(y)) % self.lattice.period as u128) as u64;
+        self.lattice.get(pair(xy, zigzag(z)))
+    }
+
+    /// Samples the noise in the range `[0, 1]`, interpolating between lattice points.
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (tx, ty, tz) = (smooth(x - x0), smooth(y - y0), smooth(z - z0));
+        // Coordinates saturate when converted and wrap around at the edges of `i64`.
+        let (x0, y0, z0) = (x0 as i64, y0 as i64, z0 as i64);
+        let v = |dx, dy, dz| {
+            self.get(x0.wrapping_add(dx), y0.wrapping_add(dy), z0.wrapping_add(dz)) as f64 / 2.0
+        };
+        let plane = |dz| lerp(lerp(v(0, 0, dz), v(1, 0, dz), tx),
+                              lerp(v(0, 1, dz), v(1, 1, dz), tx), ty);
+        lerp(plane(0), plane(1), tz)
+    }
+}
+
+/// Fractal noise summing octaves of different bases.
+///
+/// Each octave scales coordinates by `lacunarity` and amplitude by `persistence`
+/// compared to the previous one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fractal<T> {
+    /// The samplers of each octave.
+    pub octaves: Vec<T>,
+    /// The frequency multiplier between octaves.
+    pub lacunarity: f64,
+    /// The amplitude multiplier between octaves.
+    pub persistence: f64,
+}
+
+/// Fractal noise in 2D.
+pub type Fractal2D = Fractal<Noise2D>;
+/// Fractal noise in 3D.
+pub type Fractal3D = Fractal<Noise3D>;
+
+impl<T> Fractal<T> {
+    /// Sums octaves, normalized to the range `[0, 1]`.
+    fn sum<F: Fn(&T, f64) -> f64>(&self, f: F) -> f64 {
+        let (mut sum, mut total, mut amplitude, mut frequency) = (0.0, 0.0, 1.0, 1.0);
+        for octave in &self.octaves {
+            sum += amplitude * f(octave, frequency);
+            total += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+        if total > 0.0 {sum / total} else {0.0}
+    }
+}
+
+impl Fractal<Noise2D> {
+    /// Creates fractal noise with one octave per base.
+    pub fn new(bases: &[Base]) -> Result<Fractal2D, TrinoiseError> {
+        Ok(Fractal {
+            octaves: bases.iter().map(|&b| Noise2D::new(b)).collect::<Result<_, _>>()?,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        })
+    }
+
+    /// Samples the noise in the range `[0, 1]`.
+    pub fn sample(&self, x: f64, y: f64) -> f64 {
+        self.sum(|n, f| n.sample(x * f, y * f))
+    }
+}
+
+impl Fractal<Noise3D> {
+    /// Creates fractal noise with one octave per base.
+    pub fn new(bases: &[Base]) -> Result<Fractal3D, TrinoiseError> {
+        Ok(Fractal {
+            octaves: bases.iter().map(|&b| Noise3D::new(b)).collect::<Result<_, _>>()?,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        })
+    }
+
+    /// Samples the noise in the range `[0, 1]`.
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.sum(|n, f| n.sample(x * f, y * f, z * f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neighborhood_of;
+
+    #[test]
+    fn noise() {
+        assert_eq!((0..5).map(zigzag).collect::<Vec<_>>(), vec![0, 2, 4, 6, 8]);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(pair(u64::MAX, u64::MAX), u128::MAX);
+        // Pairs `(0, 0), (0, 1), (1, 0), (1, 1)` map to `0, 1, 2, 3`.
+        assert_eq!([pair(0, 0), pair(0, 1), pair(1, 0), pair(1, 1)], [0, 1, 2, 3]);
+        assert_eq!([pair(2, 1), pair(2, 3)], [7, 11]);
+
+        let base = Base::new(4).unwrap();
+        let n = Noise2D::new(base).unwrap();
+        // Indices `1, 3, 7, 11` lie in neighborhoods with different `tri` values.
+        assert_eq!(n.get(0, 0), neighborhood_of(0, 4).unwrap().0.tri);
+        assert_eq!(n.get(0, -1), 1);
+        assert_eq!(n.get(-1, -1), 0);
+        assert_eq!(n.get(1, -1), 2);
+        assert_eq!(n.get(1, -2), 0);
+        for &(x, y, v) in &[(0, -1, 1), (-1, -1, 3), (1, -1, 7), (1, -2, 11)] {
+            assert_eq!(n.get(x, y), neighborhood_of(v, 4).unwrap().0.tri);
+        }
+        // Wraps around after `4^4`.
+        assert_eq!(n.get(0, 8), neighborhood_of(0, 4).unwrap().0.tri);
+        for i in 0..50 {
+            let (x, y) = (i as f64 * 0.37 - 5.0, i as f64 * 0.91);
+            let s = n.sample(x, y);
+            assert!((0.0..=1.0).contains(&s));
+        }
+        assert_eq!(n.sample(3.0, -2.0), n.get(3, -2) as f64 / 2.0);
+
+        let n = Noise3D::new(base).unwrap();
+        assert_eq!(n.sample(1.0, 2.0, 3.0), n.get(1, 2, 3) as f64 / 2.0);
+        assert!((0.0..=1.0).contains(&n.sample(0.5, -1.5, 2.25)));
+
+        // Huge coordinates saturate instead of overflowing.
+        for &(x, y) in &[(1e19, 0.0), (-1e19, 0.5), (f64::MAX, f64::MIN), (9.3e18, -9.3e18)] {
+            assert!((0.0..=1.0).contains(&Noise2D::new(base).unwrap().sample(x, y)));
+            assert!((0.0..=1.0).contains(&n.sample(x, y, -x)));
+        }
+
+        let bases = [Base::new(3).unwrap(), Base::new(5).unwrap(), Base::new(7).unwrap()];
+        let f = Fractal2D::new(&bases).unwrap();
+        assert_eq!(f.octaves.len(), 3);
+        assert!((0.0..=1.0).contains(&f.sample(12.3, 4.56)));
+        let f = Fractal3D::new(&bases).unwrap();
+        assert!((0.0..=1.0).contains(&f.sample(12.3, 4.56, -7.0)));
+
+        assert_eq!(Noise2D::new(Base::new(16).unwrap()), Err(TrinoiseError::Overflow));
+    }
+}
@@ -0,0 +1,72 @@
+//! Multi-threaded signature generation.
+
+use std::thread;
+
+use crate::{neighborhood_len, tri, Counter};
+
+/// Calculates signature of successors with shared aligned positions, using multiple threads.
+///
+/// The numbers from `0` to `n^n` are split into one chunk per thread.
+/// Each thread produces the values of the neighborhoods that start within its chunk,
+/// following the last neighborhood into the next chunk when it straddles the boundary.
+/// The output is identical to `signature`.
+///
+/// When `threads` is `0`, the available parallelism is used.
+/// At most `n^n` threads are spawned.
+pub fn parallel_signature(base: u8, threads: usize) -> Vec<u8> {
+    let threads = if threads == 0 {
+        thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    } else {threads};
+    let end = (base as u64).pow(base as u32);
+    // Never use more threads than numbers, such that no chunk is empty.
+    let threads = (threads as u64).min(end).max(1);
+    let chunk = end.div_ceil(threads);
+    let parts: Vec<Vec<u8>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..end.div_ceil(chunk))
+            .map(|i| {
+                let start = i * chunk;
+                let stop = ((i + 1) * chunk).min(end);
+                s.spawn(move || chunk_signature(base, start, stop, end))
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    parts.concat()
+}
+
+/// Calculates the values of neighborhoods starting within `start..stop`.
+fn chunk_signature(base: u8, start: u64, stop: u64, end: u64) -> Vec<u8> {
+    let mut r = vec![];
+    if start >= stop {return r}
+    let mut v = start;
+    let mut counter = Counter::new(v, base);
+    if v > 0 && Counter::new(v - 1, base).aligned() == counter.aligned() {
+        // Skip the rest of the neighborhood that started in the previous chunk.
+        v += neighborhood_len(&mut counter, v, end);
+    }
+    while v < stop {
+        let len = neighborhood_len(&mut counter, v, end);
+        v += len;
+        r.push(tri((len - 1) as u8, base));
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature;
+
+    #[test]
+    fn parallel() {
+        for base in 0..7 {
+            let s = signature(base);
+            for threads in 0..6 {
+                assert_eq!(parallel_signature(base, threads), s);
+            }
+            assert_eq!(parallel_signature(base, 37), s);
+        }
+        // Far more threads than numbers only spawns one thread per number.
+        assert_eq!(parallel_signature(3, 50_000), signature(3));
+    }
+}
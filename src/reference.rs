@@ -0,0 +1,172 @@
+//! Custom reference maps to count aligned positions against.
+
+use crate::{Counter, Neighborhood, Node, TrinoiseError};
+
+/// A reference map that nodes are compared against when counting aligned positions.
+///
+/// This is any node, i.e. an endofunction of `[n]` written as the list `[f(0), f(1), ..., f(n-1)]`.
+/// The first position is compared against the most significant digit.
+///
+/// The crate root uses the identity map `[0, 1, ..., n-1]`.
+/// Other references give different reachability trees and neighborhood sizes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Reference {
+    node: Node,
+}
+
+impl Reference {
+    /// Creates a new reference map.
+    ///
+    /// The list is validated by `Node::new`.
+    pub fn new(map: Vec<u8>) -> Result<Reference, TrinoiseError> {
+        Ok(Reference {node: Node::new(map)?})
+    }
+
+    /// Returns the identity map `[0, 1, ..., n-1]`.
+    pub fn identity(base: u8) -> Reference {
+        Reference {node: Node::identity(base)}
+    }
+
+    /// Returns the node used as reference map.
+    pub fn node(&self) -> &Node {&self.node}
+
+    /// Returns the base of the reference map.
+    pub fn base(&self) -> u8 {self.node.base()}
+
+    /// Returns the list of values of the reference map.
+    pub fn map(&self) -> &[u8] {self.node.positions()}
+
+    /// Returns `true` if this is the identity map.
+    pub fn is_identity(&self) -> bool {self.node.aligned() == self.node.base()}
+
+    /// Returns `true` if the reference map is a permutation.
+    pub fn is_permutation(&self) -> bool {self.node.is_permutation()}
+
+    /// Counts the number of aligned positions to the reference map.
+    pub fn aligned(&self, v: u64) -> u8 {
+        Counter::with_reference(&v, self).aligned()
+    }
+
+    /// Returns the number of successors that share number of aligned positions.
+    ///
+    /// Unlike `next` in the crate root, this does not count successors after `n^n - 1`,
+    /// since neighborhoods might wrap around for other references.
+    pub fn next(&self, v: u64) -> u64 {
+        let end = (self.base() as u64).pow(self.base() as u32);
+        if v >= end {return 0}
+        let mut c = Counter::with_reference(&v, self);
+        crate::neighborhood_len(&mut c, v, end) - 1
+    }
+
+    /// Calculates signature of successors with shared aligned positions.
+    ///
+    /// Neighborhood sizes are projected to `0`, `1` or `2` by `size_tri`,
+    /// which is lossy for references that break the sizes `{1, n - 1, n}`.
+    pub fn signature(&self) -> Vec<u8> {
+        self.neighborhoods().map(|n| n.tri).collect()
+    }
+
+    /// Returns an iterator over the local neighborhoods from `0` to `n^n`.
+    pub fn neighborhoods(&self) -> ReferenceNeighborhoods {
+        ReferenceNeighborhoods {
+            counter: Counter::with_reference(&0u64, self),
+            v: 0,
+            end: (self.base() as u64).pow(self.base() as u32),
+        }
+    }
+}
+
+/// Maps neighborhood sizes `1 => 0, n - 1 => 1` and every other size to `2`.
+///
+/// This agrees with `tri` for sizes up to `n`, which covers `{1, n - 1, n}` of the identity map,
+/// but is lossy for other references, since sizes other than `1` and `n - 1`
+/// can not be told apart from `n`.
+/// Use `ReferenceReport::histogram` in the `search` module to compare raw sizes.
+pub fn size_tri(len: u64, base: u8) -> u8 {
+    if len == 1 {0} else if len + 1 == base as u64 {1} else {2}
+}
+
+/// Iterates over the local neighborhoods from `0` to `n^n` relative to a reference map.
+///
+/// The `tri` value of each neighborhood is computed by `size_tri`.
+#[derive(Clone, Debug)]
+pub struct ReferenceNeighborhoods {
+    counter: Counter,
+    v: u64,
+    end: u64,
+}
+
+impl Iterator for ReferenceNeighborhoods {
+    type Item = Neighborhood;
+    fn next(&mut self) -> Option<Neighborhood> {
+        if self.v >= self.end {return None}
+        let start = self.v;
+        let a = self.counter.aligned();
+        let len = crate::neighborhood_len(&mut self.counter, start, self.end);
+        self.v += len;
+        Some(Neighborhood {start, len, aligned: a, tri: size_tri(len, self.counter.base())})
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = (self.end - self.v) as usize;
+        (if left > 0 {1} else {0}, Some(left))
+    }
+}
+
+impl std::iter::FusedIterator for ReferenceNeighborhoods {}
+
+impl From<Node> for Reference {
+    fn from(node: Node) -> Reference {Reference {node}}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aligned, next, signature, tri, Neighborhoods};
+
+    #[test]
+    fn reference() {
+        for base in 2..6 {
+            let id = Reference::identity(base);
+            assert!(id.is_identity() && id.is_permutation());
+            assert_eq!(id.signature(), signature(base));
+            assert_eq!(id.neighborhoods().collect::<Vec<_>>(),
+                       Neighborhoods::from_base(base).collect::<Vec<_>>());
+            let end = (base as u64).pow(base as u32);
+            for v in 0..end - 1 {
+                assert_eq!(id.aligned(v), aligned(v, base));
+                assert_eq!(id.next(v), next(v, base) as u64);
+            }
+        }
+
+        assert_eq!(Reference::new(vec![0, 3, 1]), Err(TrinoiseError::IndexOutOfRange));
+        assert_eq!(Reference::from("210".parse::<Node>().unwrap()).map(), &[2, 1, 0]);
+        let r = Reference::new(vec![2, 1, 0]).unwrap();
+        assert!(!r.is_identity() && r.is_permutation());
+        assert_eq!(r.aligned(21), 3);
+        let mut v = 0;
+        let mut sizes = vec![];
+        for n in r.neighborhoods() {
+            for i in 0..n.len {assert_eq!(r.aligned(n.start + i), n.aligned)}
+            assert_eq!(n.start, v);
+            v += n.len;
+            sizes.push(n.len);
+        }
+        assert_eq!(v, 27);
+        assert_eq!(sizes, vec![1, 2, 1, 3, 2, 1, 2, 1, 3, 2, 1, 2, 1, 3, 2]);
+
+        let r = Reference::new(vec![0, 2, 1]).unwrap();
+        let s = r.signature();
+        assert_eq!(s.len(), 24);
+        assert!(s.iter().all(|&x| x < 2));
+        assert_eq!(r.next(2), 1);
+        assert_eq!(r.next(26), 0);
+
+        for base in 2..8 {
+            for len in 1..=base as u64 {
+                assert_eq!(size_tri(len, base), tri((len - 1) as u8, base));
+            }
+            assert_eq!(size_tri(1000, base), 2);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Exhaustive search over reference maps.
+//!
+//! The identity map is chosen to keep local neighborhood sizes in `{1, n - 1, n}`.
+//! This module tries every permutation, or every endofunction, as reference map
+//! to characterize which references preserve this property.
+
+use std::fmt;
+
+use crate::Reference;
+
+/// The neighborhood structure relative to some reference map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReferenceReport {
+    /// The reference map.
+    pub reference: Reference,
+    /// The distinct neighborhood sizes, in ascending order.
+    pub sizes: Vec<u64>,
+    /// The number of neighborhoods of each size, in ascending order of size.
+    pub histogram: Vec<(u64, u64)>,
+    /// The frequencies of `0`, `1` and `2` in the signature.
+    ///
+    /// This is a lossy projection of `histogram` by `size_tri`,
+    /// where every size other than `1` and `n - 1` counts as `2`.
+    pub frequencies: [u64; 3],
+}
+
+impl ReferenceReport {
+    /// Computes the neighborhood structure of a reference map.
+    pub fn new(reference: Reference) -> ReferenceReport {
+        let mut histogram: Vec<(u64, u64)> = vec![];
+        let mut frequencies = [0; 3];
+        for n in reference.neighborhoods() {
+            match histogram.binary_search_by_key(&n.len, |&(s, _)| s) {
+                Ok(i) => histogram[i].1 += 1,
+                Err(i) => histogram.insert(i, (n.len, 1)),
+            }
+            frequencies[n.tri as usize] += 1;
+        }
+        let sizes = histogram.iter().map(|&(s, _)| s).collect();
+        ReferenceReport {reference, sizes, histogram, frequencies}
+    }
+
+    /// Returns `true` if all neighborhood sizes are `1`, `n - 1` or `n`.
+    pub fn preserves_sizes(&self) -> bool {
+        let n = self.reference.base() as u64;
+        self.sizes.iter().all(|&s| s == 1 || s + 1 == n || s == n)
+    }
+}
+
+impl fmt::Display for ReferenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &x in self.reference.map() {write!(f, "{} ", x)?}
+        write!(f, "sizes {:?} frequencies {:?} {}", self.sizes, self.frequencies,
+               if self.preserves_sizes() {"preserved"} else {"destroyed"})
+    }
+}
+
+/// Reports the neighborhood structure of every permutation as reference map.
+///
+/// Permutations are visited in lexicographic order, starting with the identity map.
+pub fn search_permutations(base: u8) -> Vec<ReferenceReport> {
+    let mut map: Vec<u8> = (0..base).collect();
+    let mut res = vec![];
+    loop {
+        res.push(ReferenceReport::new(Reference::new(map.clone()).unwrap()));
+        // Move to the next permutation in lexicographic order.
+        let i = match (1..map.len()).rev().find(|&i| map[i - 1] < map[i]) {
+            Some(i) => i - 1,
+            None => break,
+        };
+        let j = (i + 1..map.len()).rev().find(|&j| map[j] > map[i]).unwrap();
+        map.swap(i, j);
+        map[i + 1..].reverse();
+    }
+    res
+}
+
+/// Reports the neighborhood structure of every endofunction as reference map.
+///
+/// Endofunctions are visited in lexicographic order.
+pub fn search_endofunctions(base: u8) -> Vec<ReferenceReport> {
+    let mut map = vec![0; base as usize];
+    let mut res = vec![];
+    loop {
+        res.push(ReferenceReport::new(Reference::new(map.clone()).unwrap()));
+        // Count upwards with the last position as least significant digit.
+        match map.iter().rposition(|&x| x + 1 < base) {
+            Some(i) => {
+                map[i] += 1;
+                for x in &mut map[i + 1..] {*x = 0}
+            }
+            None => break,
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search() {
+        let reports = search_permutations(4);
+        assert_eq!(reports.len(), 24);
+        assert!(reports[0].reference.is_identity());
+        assert_eq!(reports[0].sizes, vec![1, 3, 4]);
+        assert_eq!(reports[0].frequencies, [44, 44, 20]);
+        assert_eq!(reports[0].histogram, vec![(1, 44), (3, 44), (4, 20)]);
+        for r in &reports {
+            assert_eq!(r.histogram.iter().map(|&(s, c)| s * c).sum::<u64>(), 256);
+            assert_eq!(r.histogram.iter().map(|&(_, c)| c).sum::<u64>(),
+                       r.frequencies.iter().sum::<u64>());
+        }
+        // The property is preserved when the last position maps to `0` or `n - 1`.
+        for r in &reports {
+            let last = *r.reference.map().last().unwrap();
+            assert_eq!(r.preserves_sizes(), last == 0 || last == 3);
+        }
+
+        let reports = search_endofunctions(3);
+        assert_eq!(reports.len(), 27);
+        assert_eq!(reports[5].reference.map(), &[0, 1, 2]);
+        assert_eq!(reports.iter().filter(|r| r.reference.is_permutation()).count(), 6);
+        assert_eq!(format!("{}", reports[5]), "0 1 2 sizes [1, 2, 3] frequencies [6, 6, 3] preserved");
+    }
+}
@@ -0,0 +1,127 @@
+//! Post-filtering of nodes to sets.
+//!
+//! A node becomes a subset of `[n]` by removing redundant members,
+//! e.g. `[0, 0, 0]` becomes `{0}`.
+//! Sets are represented as bitmasks, where bit `i` is set when `i` is a member.
+//! Nodes with the same image set are isomorphic as sets,
+//! so picking one representative per set enumerates the powerset.
+
+use std::collections::BTreeMap;
+
+use crate::tree::Tree;
+use crate::{depth, Base, Counter, Node, TrinoiseError};
+
+/// Returns the image set of a node as a bitmask.
+///
+/// Returns `TrinoiseError::Overflow` if the base is larger than `64`.
+pub fn image_set(node: &Node) -> Result<u64, TrinoiseError> {
+    if node.base() > 64 {return Err(TrinoiseError::Overflow)}
+    Ok(node.positions().iter().fold(0, |mask, &x| mask | 1 << x))
+}
+
+/// Returns the members of a set bitmask in increasing order.
+pub fn members(set: u64) -> Vec<u8> {
+    (0..64).filter(|&i| set >> i & 1 == 1).collect()
+}
+
+/// Groups all nodes of some base by their image set.
+///
+/// Nodes are referred to by their numeric encoding.
+/// Since all nodes are stored in memory, this is only practical for small bases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetIndex {
+    base: u8,
+    groups: BTreeMap<u64, Vec<u64>>,
+}
+
+impl SetIndex {
+    /// Builds the index by enumerating all nodes.
+    ///
+    /// Returns `TrinoiseError::Overflow` if the number of nodes is larger than `Tree::MAX_NODES`.
+    pub fn new(base: Base) -> Result<SetIndex, TrinoiseError> {
+        let end = base.end()?;
+        if end > Tree::MAX_NODES {return Err(TrinoiseError::Overflow)}
+        let mut counter = Counter::new(0, base.get());
+        let mut groups: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+        for v in 0..end {
+            let set = counter.digits().iter().fold(0, |mask, &x| mask | 1 << x);
+            groups.entry(set).or_default().push(v);
+            counter.increment();
+        }
+        Ok(SetIndex {base: base.get(), groups})
+    }
+
+    /// Returns the base of the nodes.
+    pub fn base(&self) -> u8 {self.base}
+
+    /// Returns the number of distinct image sets.
+    pub fn len(&self) -> usize {self.groups.len()}
+
+    /// Returns `true` if there are no image sets.
+    pub fn is_empty(&self) -> bool {self.groups.is_empty()}
+
+    /// Returns an iterator over the image sets in increasing order of bitmask.
+    pub fn sets(&self) -> impl Iterator<Item = u64> + '_ {
+        self.groups.keys().cloned()
+    }
+
+    /// Returns the nodes with some image set in increasing order.
+    pub fn nodes(&self, set: u64) -> &[u64] {
+        self.groups.get(&set).map(|g| &g[..]).unwrap_or(&[])
+    }
+
+    /// Returns the nodes with some image set that have minimal depth.
+    ///
+    /// The depth is `base - aligned`, which is the distance from the identity map.
+    pub fn representatives(&self, set: u64) -> Vec<u64> {
+        let depths: Vec<(u64, u8)> = self.nodes(set).iter()
+            .map(|&v| (v, depth(v, self.base)))
+            .collect();
+        match depths.iter().map(|&(_, d)| d).min() {
+            None => vec![],
+            Some(min) => depths.into_iter().filter(|&(_, d)| d == min).map(|(v, _)| v).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets() {
+        let n: Node = "000".parse().unwrap();
+        assert_eq!(image_set(&n), Ok(0b001));
+        assert_eq!(image_set(&"201".parse().unwrap()), Ok(0b111));
+        assert_eq!(members(0b101), vec![0, 2]);
+        assert_eq!(image_set(&Node::identity(65)), Err(TrinoiseError::Overflow));
+
+        for base in 2..6 {
+            let index = SetIndex::new(Base::new(base).unwrap()).unwrap();
+            assert_eq!(index.len(), (1 << base) - 1);
+            let mut total = 0;
+            for set in index.sets() {
+                let nodes = index.nodes(set);
+                total += nodes.len() as u64;
+                for &v in nodes {
+                    assert_eq!(image_set(&Node::from_u64(v, base).unwrap()), Ok(set));
+                }
+                let k = members(set).len() as u8;
+                let reps = index.representatives(set);
+                assert!(!reps.is_empty());
+                for v in reps {
+                    assert_eq!(Node::from_u64(v, base).unwrap().depth(), base - k);
+                }
+            }
+            assert_eq!(total, (base as u64).pow(base as u32));
+        }
+
+        let index = SetIndex::new(Base::new(3).unwrap()).unwrap();
+        assert_eq!(index.nodes(0b001), &[0]);
+        assert_eq!(index.nodes(0b111).len(), 6);
+        assert_eq!(index.representatives(0b111), vec![5]);
+        assert_eq!(index.representatives(0b011).len(), 2);
+        assert!(index.nodes(0).is_empty());
+        assert_eq!(SetIndex::new(Base::new(9).unwrap()), Err(TrinoiseError::Overflow));
+    }
+}
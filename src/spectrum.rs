@@ -0,0 +1,204 @@
+//! Discrete Fourier and spectral analysis of signatures.
+//!
+//! Signatures are mapped to balanced values `-1, 0, 1` before transforming.
+//!
+//! Nodes form blocks of `n` numbers, but one block takes on average `L / n^(n-1)` values
+//! of the signature, where `L` is the length of the signature.
+//! This is less than `2` samples, so the `n` cycle in node space is above the Nyquist frequency
+//! and not visible in signature index space.
+//! Instead, the cycle of `n` blocks shows up as a peak at `cycle_period`.
+
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+/// A complex number.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Complex {
+    /// The real part.
+    pub re: f64,
+    /// The imaginary part.
+    pub im: f64,
+}
+
+impl Complex {
+    /// Creates a new complex number.
+    pub fn new(re: f64, im: f64) -> Complex {Complex {re, im}}
+
+    /// Returns `e^(i * angle)`.
+    pub fn from_angle(angle: f64) -> Complex {Complex::new(angle.cos(), angle.sin())}
+
+    /// Returns the squared magnitude.
+    pub fn norm_sqr(self) -> f64 {self.re * self.re + self.im * self.im}
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {Complex::new(self.re + other.re, self.im + other.im)}
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {Complex::new(self.re - other.re, self.im - other.im)}
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(self.re * other.re - self.im * other.im,
+                     self.re * other.im + self.im * other.re)
+    }
+}
+
+/// Maps trits `0, 1, 2` to balanced values `-1, 0, 1`.
+pub fn balanced(trits: &[u8]) -> Vec<f64> {
+    trits.iter().map(|&t| t as f64 - 1.0).collect()
+}
+
+/// Computes the discrete Fourier transform directly in `O(n^2)`.
+pub fn dft(x: &[Complex]) -> Vec<Complex> {
+    let n = x.len();
+    (0..n).map(|k| {
+        x.iter().enumerate().fold(Complex::default(), |sum, (j, &v)| {
+            sum + v * Complex::from_angle(-2.0 * PI * ((j * k) % n) as f64 / n as f64)
+        })
+    }).collect()
+}
+
+/// Computes the discrete Fourier transform in place in `O(n log n)`.
+///
+/// Panics if the length is not a power of two.
+pub fn fft(x: &mut [Complex]) {
+    let n = x.len();
+    assert!(n.is_power_of_two() || n == 0, "Expected length to be a power of two");
+    // Bit reversal permutation.
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {j ^= bit; bit >>= 1}
+        j |= bit;
+        if i < j {x.swap(i, j)}
+    }
+    let mut len = 2;
+    while len <= n {
+        let w = Complex::from_angle(-2.0 * PI / len as f64);
+        for chunk in x.chunks_mut(len) {
+            let mut wk = Complex::new(1.0, 0.0);
+            for k in 0..len / 2 {
+                let a = chunk[k];
+                let b = chunk[k + len / 2] * wk;
+                chunk[k] = a + b;
+                chunk[k + len / 2] = a - b;
+                wk = wk * w;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// The power spectral density of a signature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+    /// The number of samples after zero padding to a power of two.
+    pub len: usize,
+    /// The power of frequency bins `0..=len/2`, where bin `k` is `k / len` cycles per sample.
+    pub power: Vec<f64>,
+}
+
+impl Spectrum {
+    /// Returns the period in samples of a frequency bin.
+    pub fn period(&self, bin: usize) -> f64 {self.len as f64 / bin as f64}
+
+    /// Returns the strongest local maxima as `(period, power)`, strongest first.
+    ///
+    /// The constant component in bin `0` is ignored.
+    pub fn dominant_periods(&self, count: usize) -> Vec<(f64, f64)> {
+        let p = &self.power;
+        let mut peaks: Vec<usize> = (1..p.len())
+            .filter(|&k| p[k] > p[k - 1] && p.get(k + 1).map(|&x| p[k] >= x).unwrap_or(true))
+            .collect();
+        peaks.sort_by(|&a, &b| p[b].partial_cmp(&p[a]).unwrap());
+        peaks.into_iter().take(count).map(|k| (self.period(k), p[k])).collect()
+    }
+
+    /// Returns the spectral flatness, ignoring bin `0`.
+    ///
+    /// This is the geometric mean divided by the arithmetic mean of power,
+    /// which is near `1` for white noise and near `0` for structured signals.
+    pub fn flatness(&self) -> f64 {
+        let p = &self.power[1.min(self.power.len())..];
+        if p.is_empty() {return 0.0}
+        let n = p.len() as f64;
+        let mean = p.iter().sum::<f64>() / n;
+        if mean == 0.0 {return 0.0}
+        let log_mean = p.iter().map(|&x| x.max(f64::MIN_POSITIVE).ln()).sum::<f64>() / n;
+        log_mean.exp() / mean
+    }
+}
+
+/// Returns the period in samples of a cycle of `n` blocks, which is `n^2` nodes.
+///
+/// This is `n L / n^(n-1)`, where `L` is the length of the signature.
+pub fn cycle_period(base: u8) -> f64 {
+    let len: u128 = crate::frequencies(base).iter().sum();
+    base as f64 * len as f64 / (base as f64).powi(base as i32 - 1)
+}
+
+/// Estimates the power spectral density of a signature.
+///
+/// The mean is subtracted before the balanced values are zero padded to a power of two,
+/// such that the constant component does not leak into other bins through padding.
+/// Power is normalized by the number of samples in the signature.
+pub fn spectrum(signature: &[u8]) -> Spectrum {
+    let len = signature.len().next_power_of_two();
+    let values = balanced(signature);
+    let mean = values.iter().sum::<f64>() / values.len().max(1) as f64;
+    let mut x: Vec<Complex> = values.into_iter()
+        .map(|v| Complex::new(v - mean, 0.0)).collect();
+    x.resize(len, Complex::default());
+    fft(&mut x);
+    let n = signature.len().max(1) as f64;
+    Spectrum {
+        len,
+        power: x[..len / 2 + 1].iter().map(|c| c.norm_sqr() / n).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature;
+
+    #[test]
+    fn spectral() {
+        let x: Vec<Complex> = (0..16).map(|i| Complex::new((i * 7 % 5) as f64, (i % 3) as f64)).collect();
+        let mut y = x.clone();
+        fft(&mut y);
+        for (a, b) in dft(&x).iter().zip(&y) {assert!((*a - *b).norm_sqr() < 1e-9)}
+
+        // Period of 4 samples.
+        let trits: Vec<u8> = (0..64).map(|i| [0, 1, 2, 1][i % 4]).collect();
+        let s = spectrum(&trits);
+        assert_eq!(s.len, 64);
+        assert_eq!(s.power.len(), 33);
+        assert_eq!(s.dominant_periods(1)[0].0, 4.0);
+        assert!(s.flatness() < 0.1);
+
+        let sig = signature(5);
+        let s = spectrum(&sig);
+        assert_eq!(s.len, 2048);
+        let total: f64 = s.power.iter().sum();
+        assert!(total > 0.0);
+        assert!(!s.dominant_periods(3).is_empty());
+        assert!((0.0..=1.0).contains(&s.flatness()));
+        // The mean is removed, so there is no constant component.
+        assert!(s.power[0] < 1e-9);
+
+        // The cycle of `n` blocks is the strongest peak, within one frequency bin.
+        assert_eq!(cycle_period(6), 6.0 * 13998.0 / 7776.0);
+        for base in 6..8 {
+            let s = spectrum(&signature(base));
+            let bin = s.len as f64 / s.dominant_periods(1)[0].0;
+            assert!((bin - s.len as f64 / cycle_period(base)).abs() <= 1.0);
+        }
+    }
+}
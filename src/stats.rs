@@ -0,0 +1,180 @@
+//! Statistical tests for trinoise sequences.
+//!
+//! The functions take any sequence of trits `0`, `1` and `2`, e.g. a signature.
+//! Counting panics on values other than trits.
+
+use std::collections::HashMap;
+
+/// Statistics of gaps between occurrences of a value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gaps {
+    /// The number of gaps, one less than the occurrences.
+    pub count: u64,
+    /// The smallest gap.
+    pub min: u64,
+    /// The largest gap.
+    pub max: u64,
+    /// The mean gap.
+    pub mean: f64,
+    /// The variance of gaps.
+    pub variance: f64,
+    /// The number of gaps of each size, indexed by size.
+    pub histogram: Vec<u64>,
+}
+
+/// The results of the statistical tests.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// The number of trits.
+    pub len: usize,
+    /// The frequencies of `0`, `1` and `2`.
+    pub counts: [u64; 3],
+    /// The chi-square statistic against a uniform distribution.
+    pub chi_square: f64,
+    /// The probability of a chi-square statistic at least as large for uniform trits.
+    pub chi_square_p: f64,
+    /// The serial correlation at each lag.
+    pub serial_correlation: Vec<(usize, f64)>,
+    /// The entropy in bits of blocks of size `1, 2, ..., k`.
+    pub block_entropy: Vec<f64>,
+    /// The number of runs of each length for `0`, `1` and `2`, indexed by length.
+    pub run_lengths: [Vec<u64>; 3],
+    /// The gaps between occurrences of `2`.
+    pub gaps: Gaps,
+}
+
+/// Runs all statistical tests.
+///
+/// Serial correlation is computed at each lag and block entropy for block sizes `1..=max_block`.
+pub fn analyze(trits: &[u8], lags: &[usize], max_block: usize) -> Report {
+    let counts = counts(trits);
+    let chi = chi_square(trits);
+    Report {
+        len: trits.len(),
+        counts,
+        chi_square: chi,
+        // For 2 degrees of freedom, the tail of the chi-square distribution is `e^(-x/2)`.
+        chi_square_p: (-chi / 2.0).exp(),
+        serial_correlation: lags.iter().map(|&lag| (lag, serial_correlation(trits, lag))).collect(),
+        block_entropy: (1..=max_block).map(|k| block_entropy(trits, k)).collect(),
+        run_lengths: run_lengths(trits),
+        gaps: gaps(trits, 2),
+    }
+}
+
+/// Counts the frequencies of `0`, `1` and `2`.
+pub fn counts(trits: &[u8]) -> [u64; 3] {
+    let mut p = [0; 3];
+    for &t in trits {p[t as usize] += 1}
+    p
+}
+
+/// Computes the chi-square statistic against a uniform distribution of trits.
+pub fn chi_square(trits: &[u8]) -> f64 {
+    if trits.is_empty() {return 0.0}
+    let expected = trits.len() as f64 / 3.0;
+    counts(trits).iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum()
+}
+
+/// Computes the correlation between trits and the trits `lag` positions later.
+///
+/// Returns `0` when the correlation is undefined, e.g. for a constant sequence.
+pub fn serial_correlation(trits: &[u8], lag: usize) -> f64 {
+    if lag >= trits.len() {return 0.0}
+    let n = (trits.len() - lag) as f64;
+    let a = &trits[..trits.len() - lag];
+    let b = &trits[lag..];
+    let mean = |s: &[u8]| s.iter().map(|&x| x as f64).sum::<f64>() / n;
+    let (ma, mb) = (mean(a), mean(b));
+    let (mut cov, mut va, mut vb) = (0.0, 0.0, 0.0);
+    for (&x, &y) in a.iter().zip(b) {
+        let (dx, dy) = (x as f64 - ma, y as f64 - mb);
+        cov += dx * dy;
+        va += dx * dx;
+        vb += dy * dy;
+    }
+    if va == 0.0 || vb == 0.0 {0.0} else {cov / (va * vb).sqrt()}
+}
+
+/// Computes the entropy in bits of overlapping blocks of `k` trits.
+///
+/// The maximum is `k * log2(3)` for uniform random trits.
+pub fn block_entropy(trits: &[u8], k: usize) -> f64 {
+    if k == 0 || k > trits.len() {return 0.0}
+    let mut blocks: HashMap<&[u8], u64> = HashMap::new();
+    for w in trits.windows(k) {*blocks.entry(w).or_insert(0) += 1}
+    let total = (trits.len() - k + 1) as f64;
+    blocks.values().map(|&c| {let p = c as f64 / total; -p * p.log2()}).sum()
+}
+
+/// Counts runs of equal trits, by value and length.
+pub fn run_lengths(trits: &[u8]) -> [Vec<u64>; 3] {
+    let mut runs = [vec![], vec![], vec![]];
+    let mut i = 0;
+    while i < trits.len() {
+        let t = trits[i];
+        let len = trits[i..].iter().take_while(|&&x| x == t).count();
+        let r = &mut runs[t as usize];
+        if r.len() <= len {r.resize(len + 1, 0)}
+        r[len] += 1;
+        i += len;
+    }
+    runs
+}
+
+/// Computes statistics of the distances between consecutive occurrences of a value.
+pub fn gaps(trits: &[u8], value: u8) -> Gaps {
+    let positions: Vec<usize> = trits.iter().enumerate()
+        .filter(|&(_, &t)| t == value).map(|(i, _)| i).collect();
+    let gaps: Vec<u64> = positions.windows(2).map(|w| (w[1] - w[0]) as u64).collect();
+    let count = gaps.len() as u64;
+    let mut histogram = vec![0; gaps.iter().max().map(|&m| m as usize + 1).unwrap_or(0)];
+    for &g in &gaps {histogram[g as usize] += 1}
+    let mean = if count > 0 {gaps.iter().sum::<u64>() as f64 / count as f64} else {0.0};
+    let variance = if count > 0 {
+        gaps.iter().map(|&g| (g as f64 - mean).powi(2)).sum::<f64>() / count as f64
+    } else {0.0};
+    Gaps {
+        count,
+        min: gaps.iter().cloned().min().unwrap_or(0),
+        max: gaps.iter().cloned().max().unwrap_or(0),
+        mean,
+        variance,
+        histogram,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature;
+
+    #[test]
+    fn statistics() {
+        let s = signature(4);
+        let r = analyze(&s, &[1, 2, 3], 4);
+        assert_eq!(r.len, 108);
+        assert_eq!(r.counts, [44, 44, 20]);
+        assert!((r.chi_square - 384.0 / 36.0).abs() < 1e-9);
+        assert!(r.chi_square_p > 0.0 && r.chi_square_p < 0.01);
+        assert_eq!(r.serial_correlation.len(), 3);
+        assert!(r.serial_correlation.iter().all(|&(_, c)| (-1.0..=1.0).contains(&c)));
+        assert_eq!(r.block_entropy.len(), 4);
+        assert!(r.block_entropy.windows(2).all(|w| w[0] <= w[1]));
+        assert!(r.block_entropy[0] <= 3f64.log2());
+        for (t, runs) in r.run_lengths.iter().enumerate() {
+            let total: u64 = runs.iter().enumerate().map(|(l, &c)| l as u64 * c).sum();
+            assert_eq!(total, r.counts[t]);
+        }
+        assert_eq!(r.gaps.count, 19);
+
+        let g = gaps(&[2, 0, 2, 1, 1, 2, 2], 2);
+        assert_eq!((g.count, g.min, g.max), (3, 1, 3));
+        assert_eq!(g.histogram, vec![0, 1, 1, 1]);
+        assert!((g.mean - 2.0).abs() < 1e-9);
+        assert_eq!(run_lengths(&[1, 1, 0, 1]), [vec![0, 1], vec![0, 1, 1], vec![]]);
+        assert!((serial_correlation(&[0, 1, 2, 0, 1, 2, 0], 3) - 1.0).abs() < 1e-9);
+        assert_eq!(serial_correlation(&[1, 1, 1], 1), 0.0);
+        assert_eq!(block_entropy(&[0, 1, 0, 1], 1), 1.0);
+    }
+}
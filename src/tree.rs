@@ -0,0 +1,178 @@
+//! Explicit construction of the reachability tree.
+//!
+//! The tree is rooted at the identity map.
+//! Every other node has a canonical parent,
+//! which is the node where the last unaligned position is reset to the identity map.
+//! Following parents back to the root undoes `Node::path_from_identity` step by step,
+//! so the depth of a node is `base - aligned`.
+
+use std::io::{self, Write};
+
+use crate::{image, Base, Neighborhoods, Node, TrinoiseError};
+
+/// Returns the canonical parent of a node in the reachability tree.
+///
+/// Returns `None` for the identity map, which is the root.
+pub fn parent(node: &Node) -> Option<Node> {
+    let pos = last_unaligned(node)?;
+    Some(node.modify(pos, pos).unwrap())
+}
+
+/// Returns the children of a node in the reachability tree.
+///
+/// These are the nodes that have this node as canonical parent,
+/// obtained by modifying an aligned position after the last unaligned position.
+pub fn children(node: &Node) -> Vec<Node> {
+    let start = last_unaligned(node).map(|p| p + 1).unwrap_or(0);
+    let mut res = vec![];
+    for pos in start..node.base() {
+        for value in (0..node.base()).filter(|&x| x != pos) {
+            res.push(node.modify(pos, value).unwrap());
+        }
+    }
+    res
+}
+
+fn last_unaligned(node: &Node) -> Option<u8> {
+    (0..node.base()).rev().find(|&i| node.get(i) != i)
+}
+
+/// The reachability tree of all nodes for some base.
+///
+/// Nodes are referred to by their numeric encoding.
+/// Since the whole tree is stored in memory, this is only practical for small bases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tree {
+    base: u8,
+    root: u64,
+    parents: Vec<u64>,
+    depths: Vec<u8>,
+    // Nodes in breadth-first order from the root.
+    order: Vec<u64>,
+}
+
+impl Tree {
+    /// The largest number of nodes that a tree is built for.
+    ///
+    /// This allows base `8` with `8^8` nodes, which takes about 300 MB.
+    pub const MAX_NODES: u64 = 1 << 24;
+
+    /// Builds the tree by breadth-first search from the identity map.
+    ///
+    /// Returns `TrinoiseError::Overflow` if the number of nodes is larger than `Tree::MAX_NODES`.
+    pub fn new(base: Base) -> Result<Tree, TrinoiseError> {
+        let end = base.end()?;
+        if end > Tree::MAX_NODES {return Err(TrinoiseError::Overflow)}
+        let base = base.get();
+        let root = Node::identity(base);
+        let root_index = root.to_u64().unwrap();
+        let mut parents = vec![root_index; end as usize];
+        let mut depths = vec![0; end as usize];
+        let mut order = Vec::with_capacity(end as usize);
+        order.push(root_index);
+        let mut i = 0;
+        while i < order.len() {
+            let v = order[i];
+            let node = Node::from_u64(v, base).unwrap();
+            for child in children(&node) {
+                let w = child.to_u64().unwrap();
+                parents[w as usize] = v;
+                depths[w as usize] = depths[v as usize] + 1;
+                order.push(w);
+            }
+            i += 1;
+        }
+        Ok(Tree {base, root: root_index, parents, depths, order})
+    }
+
+    /// Returns the base of the tree.
+    pub fn base(&self) -> u8 {self.base}
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> u64 {self.parents.len() as u64}
+
+    /// Returns `true` if there are no nodes.
+    pub fn is_empty(&self) -> bool {self.parents.is_empty()}
+
+    /// Returns the root, which is the identity map.
+    pub fn root(&self) -> u64 {self.root}
+
+    /// Returns the parent of a node, or `None` for the root and for nodes out of range.
+    pub fn parent(&self, v: u64) -> Option<u64> {
+        if v == self.root {None} else {self.parents.get(v as usize).cloned()}
+    }
+
+    /// Returns the depth of a node, or `None` for nodes out of range.
+    pub fn depth(&self, v: u64) -> Option<u8> {
+        self.depths.get(v as usize).cloned()
+    }
+
+    /// Returns the nodes in breadth-first order from the root.
+    pub fn order(&self) -> &[u64] {&self.order}
+
+    /// Writes the tree in Graphviz DOT format.
+    ///
+    /// Nodes are labeled with their position list.
+    /// When `color` is `true`, nodes are filled by the `tri` value of their neighborhood,
+    /// using the `image::RGB` palette.
+    pub fn write_dot<W: Write>(&self, w: &mut W, color: bool) -> io::Result<()> {
+        let tris = if color {Some(self.tris())} else {None};
+        writeln!(w, "digraph trinoise {{")?;
+        if color {writeln!(w, "  node [style=filled];")?}
+        for &v in &self.order {
+            let label = Node::from_u64(v, self.base).unwrap();
+            write!(w, "  {} [label=\"{}\"", v, label)?;
+            if let Some(tris) = &tris {
+                let [r, g, b] = image::RGB[tris[v as usize] as usize];
+                write!(w, ", fillcolor=\"#{:02x}{:02x}{:02x}\"", r, g, b)?;
+            }
+            writeln!(w, "];")?;
+        }
+        for &v in &self.order {
+            if let Some(p) = self.parent(v) {writeln!(w, "  {} -> {};", p, v)?}
+        }
+        writeln!(w, "}}")
+    }
+
+    fn tris(&self) -> Vec<u8> {
+        let mut tris = vec![0; self.parents.len()];
+        for n in Neighborhoods::from_base(self.base) {
+            for v in n.start..n.start + n.len {tris[v as usize] = n.tri}
+        }
+        tris
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aligned;
+
+    #[test]
+    fn tree() {
+        for base in 2..6 {
+            let tree = Tree::new(Base::new(base).unwrap()).unwrap();
+            assert_eq!(tree.len(), (base as u64).pow(base as u32));
+            assert_eq!(tree.order().len() as u64, tree.len());
+            assert_eq!(tree.parent(tree.root()), None);
+            for v in 0..tree.len() {
+                assert_eq!(tree.depth(v), Some(base - aligned(v, base)));
+                let node = Node::from_u64(v, base).unwrap();
+                let p = parent(&node).map(|p| p.to_u64().unwrap());
+                assert_eq!(tree.parent(v), p);
+                if let Some(p) = p {assert_eq!(tree.depth(p), Some(tree.depth(v).unwrap() - 1))}
+            }
+            assert_eq!(tree.depth(tree.len()), None);
+        }
+
+        let tree = Tree::new(Base::new(2).unwrap()).unwrap();
+        let mut buf = vec![];
+        tree.write_dot(&mut buf, true).unwrap();
+        let dot = String::from_utf8(buf).unwrap();
+        assert!(dot.starts_with("digraph trinoise {"));
+        assert!(dot.contains("1 [label=\"01\""));
+        assert!(dot.contains("1 -> 0;"));
+        assert_eq!(dot.matches("->").count(), 3);
+        assert_eq!(Tree::new(Base::new(9).unwrap()), Err(TrinoiseError::Overflow));
+    }
+}
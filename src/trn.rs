@@ -0,0 +1,362 @@
+//! Compact binary file format for signatures.
+//!
+//! A `.trn` file packs five trits per byte, since `3^5 = 243` fits in a byte.
+//! All numbers are little endian.
+//!
+//! | Offset | Size | Field                                          |
+//! |--------|------|------------------------------------------------|
+//! | 0      | 4    | Magic bytes `TRN\0`                            |
+//! | 4      | 1    | Format version, currently `1`                  |
+//! | 5      | 1    | Base of the signature                          |
+//! | 6      | 2    | Reserved, zero                                 |
+//! | 8      | 8    | Number of trits                                |
+//! | 16     | 4    | FNV-1a 32 bit checksum of the packed trits     |
+//! | 20     |      | Packed trits                                   |
+//!
+//! Each byte of packed trits is `t0 + 3 * t1 + 9 * t2 + 27 * t3 + 81 * t4`,
+//! where `t0` is the earliest trit.
+//! The last byte is padded with zero trits.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::SignatureIter;
+
+/// The magic bytes at the start of a `.trn` file.
+pub const MAGIC: [u8; 4] = *b"TRN\0";
+/// The current format version.
+pub const VERSION: u8 = 1;
+/// The size of the header in bytes.
+pub const HEADER_LEN: u64 = 20;
+/// The number of trits packed in a byte.
+pub const TRITS_PER_BYTE: u64 = 5;
+
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+fn fnv1a(mut hash: u32, bytes: &[u8]) -> u32 {
+    for &b in bytes {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// The header of a `.trn` file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// The format version.
+    pub version: u8,
+    /// The base of the signature.
+    pub base: u8,
+    /// The number of trits.
+    pub len: u64,
+    /// The checksum of the packed trits.
+    pub checksum: u32,
+}
+
+impl Header {
+    /// Reads a header.
+    pub fn read<R: Read>(r: &mut R) -> io::Result<Header> {
+        let mut buf = [0; HEADER_LEN as usize];
+        r.read_exact(&mut buf)?;
+        if buf[0..4] != MAGIC {return Err(invalid("Not a `.trn` file"))}
+        let version = buf[4];
+        if version != VERSION {return Err(invalid("Unsupported `.trn` format version"))}
+        let mut len = [0; 8];
+        len.copy_from_slice(&buf[8..16]);
+        let mut checksum = [0; 4];
+        checksum.copy_from_slice(&buf[16..20]);
+        Ok(Header {
+            version,
+            base: buf[5],
+            len: u64::from_le_bytes(len),
+            checksum: u32::from_le_bytes(checksum),
+        })
+    }
+
+    /// Writes a header.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut buf = [0; HEADER_LEN as usize];
+        buf[0..4].copy_from_slice(&MAGIC);
+        buf[4] = self.version;
+        buf[5] = self.base;
+        buf[8..16].copy_from_slice(&self.len.to_le_bytes());
+        buf[16..20].copy_from_slice(&self.checksum.to_le_bytes());
+        w.write_all(&buf)
+    }
+
+    /// Returns the number of bytes of packed trits.
+    pub fn data_len(&self) -> u64 {self.len.div_ceil(TRITS_PER_BYTE)}
+}
+
+/// Writes trits in the `.trn` format.
+///
+/// The trits are packed while streaming,
+/// and the header is updated with length and checksum at the end.
+pub fn write_trits<W, I>(w: &mut W, base: u8, trits: I) -> io::Result<Header>
+    where W: Write + Seek, I: IntoIterator<Item = u8>
+{
+    let start = w.stream_position()?;
+    let mut header = Header {version: VERSION, base, len: 0, checksum: FNV_OFFSET};
+    header.write(w)?;
+    let mut buf = Vec::with_capacity(1 << 16);
+    let (mut byte, mut scale) = (0u8, 1u8);
+    for t in trits {
+        if t > 2 {return Err(io::Error::new(io::ErrorKind::InvalidInput, "Expected trit"))}
+        byte += t * scale;
+        header.len += 1;
+        if header.len.is_multiple_of(TRITS_PER_BYTE) {
+            buf.push(byte);
+            byte = 0;
+            scale = 1;
+            if buf.len() == buf.capacity() {
+                header.checksum = fnv1a(header.checksum, &buf);
+                w.write_all(&buf)?;
+                buf.clear();
+            }
+        } else {
+            scale *= 3;
+        }
+    }
+    if !header.len.is_multiple_of(TRITS_PER_BYTE) {buf.push(byte)}
+    header.checksum = fnv1a(header.checksum, &buf);
+    w.write_all(&buf)?;
+    let end = w.stream_position()?;
+    w.seek(SeekFrom::Start(start))?;
+    header.write(w)?;
+    w.seek(SeekFrom::Start(end))?;
+    Ok(header)
+}
+
+/// Writes the signature of some base in the `.trn` format.
+///
+/// Panics if `n^n` does not fit in `u64`.
+pub fn write_signature<W: Write + Seek>(w: &mut W, base: u8) -> io::Result<Header> {
+    write_trits(w, base, SignatureIter::from_base(base))
+}
+
+/// Reads a signature in the `.trn` format.
+///
+/// The checksum is verified when reaching the end,
+/// returning an error as last item if it does not match.
+pub fn read_signature<R: Read>(mut r: R) -> io::Result<TrnReader<R>> {
+    let header = Header::read(&mut r)?;
+    Ok(TrnReader {
+        reader: r,
+        header,
+        buf: vec![],
+        pos: 0,
+        read: 0,
+        checksum: FNV_OFFSET,
+        failed: false,
+    })
+}
+
+/// Streams trits from a `.trn` file.
+#[derive(Debug)]
+pub struct TrnReader<R> {
+    reader: R,
+    header: Header,
+    buf: Vec<u8>,
+    pos: usize,
+    read: u64,
+    checksum: u32,
+    failed: bool,
+}
+
+impl<R> TrnReader<R> {
+    /// Returns the header of the file.
+    pub fn header(&self) -> &Header {&self.header}
+}
+
+impl<R: Read> Iterator for TrnReader<R> {
+    type Item = io::Result<u8>;
+    fn next(&mut self) -> Option<io::Result<u8>> {
+        if self.failed || self.read >= self.header.len {return None}
+        let byte_index = self.read / TRITS_PER_BYTE;
+        if byte_index as usize >= self.pos + self.buf.len() {
+            // Refill the buffer with the next chunk of packed trits.
+            self.pos += self.buf.len();
+            let n = (self.header.data_len() - self.pos as u64).min(1 << 16) as usize;
+            self.buf.resize(n, 0);
+            if let Err(err) = self.reader.read_exact(&mut self.buf) {
+                self.failed = true;
+                return Some(Err(err));
+            }
+            self.checksum = fnv1a(self.checksum, &self.buf);
+            if self.pos as u64 + n as u64 == self.header.data_len() &&
+               self.checksum != self.header.checksum {
+                self.failed = true;
+                return Some(Err(invalid("Checksum mismatch")));
+            }
+        }
+        let byte = self.buf[byte_index as usize - self.pos];
+        let t = unpack(byte, self.read % TRITS_PER_BYTE);
+        self.read += 1;
+        Some(Ok(t))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.header.len - self.read) as usize;
+        (0, Some(n))
+    }
+}
+
+impl<R: Read> std::iter::FusedIterator for TrnReader<R> {}
+
+/// Random access to trits stored in the `.trn` format.
+///
+/// Entries are looked up by computing byte offsets directly,
+/// without loading the whole file.
+#[derive(Debug)]
+pub struct TrnFile<R = File> {
+    reader: R,
+    header: Header,
+    start: u64,
+}
+
+impl TrnFile<File> {
+    /// Opens a `.trn` file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<TrnFile<File>> {
+        TrnFile::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> TrnFile<R> {
+    /// Creates a new random access reader, starting at the header.
+    ///
+    /// Returns an error if the data is shorter than the header says.
+    pub fn new(mut reader: R) -> io::Result<TrnFile<R>> {
+        let header = Header::read(&mut reader)?;
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end - start < header.data_len() {return Err(invalid("Unexpected end of `.trn` file"))}
+        Ok(TrnFile {reader, header, start})
+    }
+
+    /// Returns the header of the file.
+    pub fn header(&self) -> &Header {&self.header}
+
+    /// Returns the number of trits.
+    pub fn len(&self) -> u64 {self.header.len}
+
+    /// Returns `true` if there are no trits.
+    pub fn is_empty(&self) -> bool {self.header.len == 0}
+
+    /// Returns the trit at some index.
+    ///
+    /// Returns `None` if the index is out of range.
+    pub fn get(&mut self, index: u64) -> io::Result<Option<u8>> {
+        if index >= self.header.len {return Ok(None)}
+        self.reader.seek(SeekFrom::Start(self.start + index / TRITS_PER_BYTE))?;
+        let mut byte = [0];
+        self.reader.read_exact(&mut byte)?;
+        Ok(Some(unpack(byte[0], index % TRITS_PER_BYTE)))
+    }
+
+    /// Returns the trits within a range.
+    ///
+    /// The range is clamped to the number of trits.
+    pub fn range(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let end = range.end.min(self.header.len);
+        if range.start >= end {return Ok(vec![])}
+        let first = range.start / TRITS_PER_BYTE;
+        let last = (end - 1) / TRITS_PER_BYTE;
+        self.reader.seek(SeekFrom::Start(self.start + first))?;
+        let mut bytes = vec![0; (last - first + 1) as usize];
+        self.reader.read_exact(&mut bytes)?;
+        Ok((range.start..end)
+            .map(|i| unpack(bytes[(i / TRITS_PER_BYTE - first) as usize], i % TRITS_PER_BYTE))
+            .collect())
+    }
+
+    /// Verifies the checksum by reading all packed trits.
+    pub fn verify(&mut self) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(self.start))?;
+        let mut left = self.header.data_len();
+        let mut buf = vec![0; 1 << 16];
+        let mut checksum = FNV_OFFSET;
+        while left > 0 {
+            let n = left.min(buf.len() as u64) as usize;
+            self.reader.read_exact(&mut buf[..n])?;
+            checksum = fnv1a(checksum, &buf[..n]);
+            left -= n as u64;
+        }
+        if checksum == self.header.checksum {Ok(())} else {Err(invalid("Checksum mismatch"))}
+    }
+}
+
+/// Returns trit number `i` of a packed byte.
+fn unpack(mut byte: u8, i: u64) -> u8 {
+    for _ in 0..i {byte /= 3}
+    byte % 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::signature;
+
+    #[test]
+    fn round_trip() {
+        for base in 0..7 {
+            let mut file = Cursor::new(vec![]);
+            let header = write_signature(&mut file, base).unwrap();
+            let s = signature(base);
+            assert_eq!(header.len, s.len() as u64);
+            assert_eq!(file.get_ref().len() as u64, HEADER_LEN + header.data_len());
+            file.set_position(0);
+            let r = read_signature(&mut file).unwrap();
+            assert_eq!(*r.header(), header);
+            assert_eq!(r.collect::<io::Result<Vec<u8>>>().unwrap(), s);
+        }
+
+        let mut file = Cursor::new(vec![]);
+        write_trits(&mut file, 3, vec![2, 1, 0, 2, 2, 1]).unwrap();
+        assert_eq!(&file.get_ref()[20..], &[2 + 3 + 54 + 162, 1]);
+        file.get_mut()[21] = 2;
+        file.set_position(0);
+        let r = read_signature(&mut file).unwrap();
+        assert!(r.collect::<io::Result<Vec<u8>>>().is_err());
+        assert!(write_trits(&mut Cursor::new(vec![]), 3, vec![3]).is_err());
+
+        // Spans several chunks of buffered packed trits.
+        let trits: Vec<u8> = (0..400_003u64).map(|i| (i * i % 7 % 3) as u8).collect();
+        let mut file = Cursor::new(vec![]);
+        write_trits(&mut file, 9, trits.iter().cloned()).unwrap();
+        file.set_position(0);
+        let r = read_signature(&mut file).unwrap();
+        assert_eq!(r.collect::<io::Result<Vec<u8>>>().unwrap(), trits);
+    }
+
+    #[test]
+    fn random_access() {
+        let base = 5;
+        let s = signature(base);
+        let path = std::env::temp_dir().join(format!("trinoise-{}.trn", std::process::id()));
+        write_signature(&mut File::create(&path).unwrap(), base).unwrap();
+        let mut f = TrnFile::open(&path).unwrap();
+        assert_eq!(f.len(), s.len() as u64);
+        assert_eq!(f.header().base, base);
+        f.verify().unwrap();
+        for i in (0..s.len()).step_by(7) {assert_eq!(f.get(i as u64).unwrap(), Some(s[i]))}
+        assert_eq!(f.get(s.len() as u64).unwrap(), None);
+        assert_eq!(f.range(13..400).unwrap(), &s[13..400]);
+        assert_eq!(f.range(1090..2000).unwrap(), &s[1090..]);
+        assert_eq!(f.range(5..5).unwrap(), vec![]);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut file = Cursor::new(vec![]);
+        write_trits(&mut file, 3, vec![1; 11]).unwrap();
+        file.get_mut().pop();
+        assert!(TrnFile::new(file).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Verification of conjectures for a range of bases.
+//!
+//! Each base is enumerated with `Neighborhoods`, so the checks exercise the core algorithms.
+//! Run this whenever the core algorithms change.
+
+use std::fmt;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::{frequencies, Neighborhoods};
+
+/// A conjecture about signatures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Conjecture {
+    /// Neighborhood sizes are always `1`, `n - 1` or `n`.
+    NeighborhoodSizes,
+    /// Frequencies of `0` and `1` are equal for bases greater than `2`.
+    EqualFrequencies,
+    /// Frequency of `0` divided by frequency of `2` converges to `base - 2`.
+    ///
+    /// Checked by the error decreasing compared to the enumerated previous base.
+    /// The first base of a range compares against the closed form of the previous base.
+    RatioConvergence,
+    /// Enumerated frequencies are equal to the closed form of `frequencies`.
+    ClosedForm,
+}
+
+impl Conjecture {
+    /// All conjectures that are checked.
+    pub const ALL: [Conjecture; 4] = [
+        Conjecture::NeighborhoodSizes,
+        Conjecture::EqualFrequencies,
+        Conjecture::RatioConvergence,
+        Conjecture::ClosedForm,
+    ];
+}
+
+impl fmt::Display for Conjecture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Conjecture::NeighborhoodSizes => "neighborhood_sizes",
+            Conjecture::EqualFrequencies => "equal_frequencies",
+            Conjecture::RatioConvergence => "ratio_convergence",
+            Conjecture::ClosedForm => "closed_form",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The result of checking a conjecture for some base.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    /// The conjecture.
+    pub conjecture: Conjecture,
+    /// The base.
+    pub base: u8,
+    /// A description of the counterexample, if any.
+    pub counterexample: Option<String>,
+}
+
+/// The results of checking conjectures for a range of bases.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// The results of the checks, ordered by base.
+    pub checks: Vec<Check>,
+    /// The time used to enumerate and check each base.
+    pub timings: Vec<(u8, Duration)>,
+    /// The total time used.
+    pub total: Duration,
+}
+
+impl Report {
+    /// Returns `true` if no counterexample was found.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.counterexample.is_none())
+    }
+
+    /// Returns the first counterexample of a conjecture.
+    pub fn first_counterexample(&self, conjecture: Conjecture) -> Option<&Check> {
+        self.checks.iter()
+            .find(|c| c.conjecture == conjecture && c.counterexample.is_some())
+    }
+
+    /// Formats the report as plain text.
+    pub fn to_text(&self) -> String {
+        let mut s = String::new();
+        for &(base, time) in &self.timings {
+            s.push_str(&format!("base {} ({:.3}s)\n", base, time.as_secs_f64()));
+            for c in self.checks.iter().filter(|c| c.base == base) {
+                match c.counterexample {
+                    None => s.push_str(&format!("  {}: ok\n", c.conjecture)),
+                    Some(ref x) => s.push_str(&format!("  {}: FAILED {}\n", c.conjecture, x)),
+                }
+            }
+        }
+        for &conjecture in &Conjecture::ALL {
+            if let Some(c) = self.first_counterexample(conjecture) {
+                s.push_str(&format!("first counterexample of {}: base {}\n", conjecture, c.base));
+            }
+        }
+        s.push_str(&format!("{} ({:.3}s)\n",
+            if self.passed() {"passed"} else {"failed"}, self.total.as_secs_f64()));
+        s
+    }
+
+    /// Formats the report as JSON.
+    pub fn to_json(&self) -> String {
+        let mut s = String::from("{");
+        s.push_str(&format!("\"passed\":{},", self.passed()));
+        s.push_str(&format!("\"total_secs\":{},", self.total.as_secs_f64()));
+        s.push_str("\"timings\":[");
+        for (i, &(base, time)) in self.timings.iter().enumerate() {
+            if i > 0 {s.push(',')}
+            s.push_str(&format!("{{\"base\":{},\"secs\":{}}}", base, time.as_secs_f64()));
+        }
+        s.push_str("],\"checks\":[");
+        for (i, c) in self.checks.iter().enumerate() {
+            if i > 0 {s.push(',')}
+            s.push_str(&format!("{{\"conjecture\":\"{}\",\"base\":{},\"counterexample\":{}}}",
+                c.conjecture, c.base, json_string(c.counterexample.as_deref())));
+        }
+        s.push_str("],\"first_counterexamples\":{");
+        for (i, &conjecture) in Conjecture::ALL.iter().enumerate() {
+            if i > 0 {s.push(',')}
+            let base = self.first_counterexample(conjecture).map(|c| c.base.to_string());
+            s.push_str(&format!("\"{}\":{}", conjecture, base.as_deref().unwrap_or("null")));
+        }
+        s.push_str("}}");
+        s
+    }
+}
+
+fn json_string(s: Option<&str>) -> String {
+    match s {
+        None => "null".into(),
+        Some(s) => {
+            let mut r = String::from("\"");
+            for ch in s.chars() {
+                match ch {
+                    '"' => r.push_str("\\\""),
+                    '\\' => r.push_str("\\\\"),
+                    '\n' => r.push_str("\\n"),
+                    c if (c as u32) < 0x20 => r.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => r.push(c),
+                }
+            }
+            r.push('"');
+            r
+        }
+    }
+}
+
+/// Returns the error of frequency of `0` divided by frequency of `2` compared to `base - 2`.
+fn ratio_error(p: [u64; 3], base: u8) -> f64 {
+    (p[0] as f64 / p[2] as f64 - (base as f64 - 2.0)).abs()
+}
+
+/// Checks all conjectures for a range of bases.
+///
+/// Every base is enumerated, so this is only feasible for small bases.
+pub fn verify(bases: Range<u8>) -> Report {
+    let start = Instant::now();
+    let mut checks = vec![];
+    let mut timings = vec![];
+    // The enumerated frequencies of the previous base.
+    let mut prev: Option<[u64; 3]> = None;
+    for base in bases {
+        let time = Instant::now();
+        let n = base as u64;
+        let mut sizes = None;
+        let mut p = [0; 3];
+        for nb in Neighborhoods::from_base(base) {
+            let len = nb.len;
+            if sizes.is_none() && !(len == 1 || len + 1 == n || len == n) {
+                sizes = Some(format!("neighborhood at {} has size {}", nb.start, len));
+            }
+            p[nb.tri as usize] += 1;
+        }
+        checks.push(Check {conjecture: Conjecture::NeighborhoodSizes, base, counterexample: sizes});
+
+        let equal = if base > 2 && p[0] != p[1] {
+            Some(format!("frequencies {:?}", p))
+        } else {None};
+        checks.push(Check {conjecture: Conjecture::EqualFrequencies, base, counterexample: equal});
+
+        let ratio = if base > 3 {
+            // Only the first base of the range falls back to closed form for the previous base.
+            let (prev_p, source) = match prev {
+                Some(prev_p) => (prev_p, "enumerated"),
+                None => {
+                    let q = frequencies(base - 1);
+                    ([q[0] as u64, q[1] as u64, q[2] as u64], "closed form")
+                }
+            };
+            let (err, prev_err) = (ratio_error(p, base), ratio_error(prev_p, base - 1));
+            if err < prev_err {None} else {
+                Some(format!("error {} is not less than error {} of previous base ({})",
+                    err, prev_err, source))
+            }
+        } else {None};
+        prev = Some(p);
+        checks.push(Check {conjecture: Conjecture::RatioConvergence, base, counterexample: ratio});
+
+        let closed = frequencies(base);
+        let closed_form = if closed.iter().zip(&p).any(|(&a, &b)| a != b as u128) {
+            Some(format!("enumerated {:?} but closed form {:?}", p, closed))
+        } else {None};
+        checks.push(Check {conjecture: Conjecture::ClosedForm, base, counterexample: closed_form});
+
+        timings.push((base, time.elapsed()));
+    }
+    Report {checks, timings, total: start.elapsed()}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_small_bases() {
+        let report = verify(2..7);
+        assert!(report.passed(), "{}", report.to_text());
+        assert_eq!(report.checks.len(), 5 * Conjecture::ALL.len());
+        assert_eq!(report.first_counterexample(Conjecture::NeighborhoodSizes), None);
+        let json = report.to_json();
+        assert!(json.starts_with("{\"passed\":true,"));
+        assert!(json.contains("{\"conjecture\":\"equal_frequencies\",\"base\":3,\"counterexample\":null}"));
+        assert!(json.ends_with("\"closed_form\":null}}"));
+        assert_eq!(json_string(Some("a\"b")), "\"a\\\"b\"");
+        // The first base compares against the closed form of the previous base.
+        assert!(verify(5..7).passed());
+    }
+}